@@ -0,0 +1,78 @@
+//! Compares `Graph` lookups against the linear scans it used to do, on a
+//! graph with 100k edges. Run with `cargo bench --bench graph`.
+
+use aoc_helpers::graph::{Graph, NodeId};
+use std::collections::HashSet;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const NODES: usize = 20_000;
+const EDGES: usize = 100_000;
+
+/// Small LCG so the graph is the same every run without a rand dependency.
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 33) as usize
+    }
+}
+
+fn build() -> Graph<usize> {
+    let mut g = Graph::new();
+    let ids: Vec<NodeId> = (0..NODES).map(|v| g.add_node_with_value(v)).collect();
+    let mut rng = Lcg(12);
+    while g.edges.len() < EDGES {
+        g.add_edge(ids[rng.next() % NODES], ids[rng.next() % NODES]);
+    }
+    g
+}
+
+/// Time `f` over every id in `ids`, returning the mean time per call.
+fn time_per_call<F: FnMut(NodeId)>(ids: &[NodeId], mut f: F) -> Duration {
+    let start = Instant::now();
+    for &id in ids {
+        f(id);
+    }
+    start.elapsed() / ids.len() as u32
+}
+
+fn main() {
+    let start = Instant::now();
+    let g = build();
+    println!(
+        "built {} nodes, {} edges in {:?}",
+        g.nodes.len(),
+        g.edges.len(),
+        start.elapsed()
+    );
+
+    let ids = g.get_node_ids();
+    let sample: Vec<NodeId> = ids.iter().step_by(100).copied().collect();
+
+    let scan_lookup = time_per_call(&sample, |id| {
+        black_box(g.nodes.iter().find(|node| node.id == id));
+    });
+    let lookup = time_per_call(&ids, |id| {
+        black_box(g.get_node_from_id(id));
+    });
+    println!("get_node_from_id:   scan {scan_lookup:>12?}  indexed {lookup:>12?}");
+
+    let scan_neighbors = time_per_call(&sample, |id| {
+        let neighbors: HashSet<NodeId> = g
+            .edges
+            .keys()
+            .filter(|e| e.0 == id || e.1 == id)
+            .map(|e| if e.0 == id { e.1 } else { e.0 })
+            .collect();
+        black_box(neighbors);
+    });
+    let neighbors = time_per_call(&ids, |id| {
+        black_box(g.get_node_neighbors(id));
+    });
+    println!("get_node_neighbors: scan {scan_neighbors:>12?}  indexed {neighbors:>12?}");
+}
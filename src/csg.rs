@@ -177,12 +177,7 @@ pub fn load_input(input: &str) -> Vec<Instruction> {
     for line in input.lines() {
         let mut liter = line.split(' ');
 
-        let turn_on;
-        if liter.next().unwrap() == "on" {
-            turn_on = true;
-        } else {
-            turn_on = false;
-        }
+        let turn_on = liter.next().unwrap() == "on";
 
         let mut liter = line.split('=');
         liter.next();
@@ -0,0 +1,107 @@
+//! The DOT and Mermaid writing shared by `Graph` and `Tree`.
+use std::collections::HashSet;
+use std::io;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Format {
+    Dot,
+    Mermaid,
+}
+
+pub(crate) struct Diagram {
+    pub directed: bool,
+    /// Label for each node, which are referred to by index.
+    pub nodes: Vec<String>,
+    /// Each edge's ends, and its label if it has one.
+    pub edges: Vec<(usize, usize, Option<String>)>,
+    /// Nodes to highlight, along with the edges between consecutive ones.
+    pub path: Vec<usize>,
+}
+
+const DOT_HIGHLIGHT: &str = "color=red, penwidth=2";
+const MERMAID_HIGHLIGHT: &str = "stroke:red,stroke-width:3px";
+
+impl Diagram {
+    pub fn write<W: io::Write>(&self, w: &mut W, format: Format) -> io::Result<()> {
+        let on_path: HashSet<usize> = self.path.iter().copied().collect();
+        let mut path_edges: HashSet<(usize, usize)> = HashSet::new();
+        for step in self.path.windows(2) {
+            path_edges.insert((step[0], step[1]));
+            if !self.directed {
+                path_edges.insert((step[1], step[0]));
+            }
+        }
+        match format {
+            Format::Dot => self.write_dot(w, &on_path, &path_edges),
+            Format::Mermaid => self.write_mermaid(w, &on_path, &path_edges),
+        }
+    }
+
+    fn write_dot<W: io::Write>(
+        &self,
+        w: &mut W,
+        on_path: &HashSet<usize>,
+        path_edges: &HashSet<(usize, usize)>,
+    ) -> io::Result<()> {
+        let escape = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+        let (kind, arrow) = if self.directed {
+            ("digraph", "->")
+        } else {
+            ("graph", "--")
+        };
+        writeln!(w, "{} {{", kind)?;
+        for (i, label) in self.nodes.iter().enumerate() {
+            let mut attrs = format!("label=\"{}\"", escape(label));
+            if on_path.contains(&i) {
+                attrs = format!("{}, {}", attrs, DOT_HIGHLIGHT);
+            }
+            writeln!(w, "    n{} [{}];", i, attrs)?;
+        }
+        for (a, b, label) in &self.edges {
+            let mut attrs = vec![];
+            if let Some(label) = label {
+                attrs.push(format!("label=\"{}\"", escape(label)));
+            }
+            if path_edges.contains(&(*a, *b)) {
+                attrs.push(DOT_HIGHLIGHT.to_string());
+            }
+            if attrs.is_empty() {
+                writeln!(w, "    n{} {} n{};", a, arrow, b)?;
+            } else {
+                writeln!(w, "    n{} {} n{} [{}];", a, arrow, b, attrs.join(", "))?;
+            }
+        }
+        writeln!(w, "}}")
+    }
+
+    fn write_mermaid<W: io::Write>(
+        &self,
+        w: &mut W,
+        on_path: &HashSet<usize>,
+        path_edges: &HashSet<(usize, usize)>,
+    ) -> io::Result<()> {
+        let escape = |s: &str| s.replace('"', "#quot;");
+        let arrow = if self.directed { "-->" } else { "---" };
+        writeln!(w, "graph LR")?;
+        for (i, label) in self.nodes.iter().enumerate() {
+            writeln!(w, "    n{}[\"{}\"]", i, escape(label))?;
+        }
+        for (a, b, label) in &self.edges {
+            match label {
+                Some(label) => writeln!(w, "    n{} {}|\"{}\"| n{}", a, arrow, escape(label), b)?,
+                None => writeln!(w, "    n{} {} n{}", a, arrow, b)?,
+            }
+        }
+        let mut on_path: Vec<&usize> = on_path.iter().collect();
+        on_path.sort();
+        for i in on_path {
+            writeln!(w, "    style n{} {}", i, MERMAID_HIGHLIGHT)?;
+        }
+        for (i, (a, b, _)) in self.edges.iter().enumerate() {
+            if path_edges.contains(&(*a, *b)) {
+                writeln!(w, "    linkStyle {} {}", i, MERMAID_HIGHLIGHT)?;
+            }
+        }
+        Ok(())
+    }
+}
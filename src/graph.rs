@@ -1,64 +1,155 @@
-use std::collections::{HashMap, HashSet};
+use crate::diagram::{Diagram, Format};
+use num::{BigUint, Integer, One, Zero};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet, VecDeque};
+use std::fmt;
 use std::hash::Hash;
+use std::io;
+use std::ops::Sub;
+#[cfg(feature = "uuid")]
 use uuid::Uuid;
 
+/// Handle for a node in a `Graph`. Ids are handed out in order starting from
+/// 0 as nodes are added, so they double as the node's index into
+/// `Graph::nodes` and are the same from one run to the next.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(pub usize);
+
+impl fmt::Display for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "n{}", self.0)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Node<T: Clone + PartialEq + Eq + Hash> {
-    pub id: Uuid,
+    pub id: NodeId,
     pub value: T,
+    /// Random id that stays with the node if it's moved between graphs.
+    #[cfg(feature = "uuid")]
+    pub uuid: Uuid,
 }
 
 impl<T: Clone + PartialEq + Eq + Hash> Node<T> {
+    /// The node's `id` is filled in when it's added to a `Graph`.
     pub fn new(value: T) -> Node<T> {
-        let id = Uuid::new_v4();
-        Node { id, value }
+        Node {
+            id: NodeId(usize::MAX),
+            value,
+            #[cfg(feature = "uuid")]
+            uuid: Uuid::new_v4(),
+        }
     }
 }
 
+/// Graph of nodes joined by edges, stored as adjacency lists so finding a
+/// node or its neighbors doesn't mean scanning the whole graph. The fields
+/// are public to read, but go through the methods to add and remove nodes
+/// and edges so the lookup tables stay in sync.
+///
+/// Graphs made with `new` are undirected: an edge `(a, b)` joins `a` and `b`
+/// both ways, and adding `(b, a)` as well does nothing. Graphs made with
+/// `new_directed` only follow edges from `a` to `b`.
+///
+/// Each edge carries a value of type `E`, such as a weight, label or
+/// capacity. It defaults to `()` for graphs that don't need one, and
+/// graphs with edge data are made with `with_edge_data` or
+/// `new_directed_with_edge_data`.
 #[derive(Clone, Debug)]
-pub struct Graph<T: Clone + PartialEq + Eq + Hash> {
+pub struct Graph<T: Clone + PartialEq + Eq + Hash, E = ()> {
     pub nodes: Vec<Node<T>>,
-    pub edges: HashSet<(Uuid, Uuid)>,
-    pub idmap: HashMap<T, Uuid>,
+    pub edges: BTreeMap<(NodeId, NodeId), E>,
+    pub idmap: HashMap<T, NodeId>,
+    directed: bool,
+    /// Nodes at the far end of each node's edges.
+    outgoing: Vec<Vec<NodeId>>,
+    /// Nodes at the near end of each node's edges.
+    incoming: Vec<Vec<NodeId>>,
+    #[cfg(feature = "uuid")]
+    uuidmap: HashMap<Uuid, NodeId>,
+}
+
+impl<T: Clone + PartialEq + Eq + Hash> Default for Graph<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<T: Clone + PartialEq + Eq + Hash> Graph<T> {
     pub fn new() -> Graph<T> {
+        Graph::with_edge_data()
+    }
+
+    pub fn new_directed() -> Graph<T> {
+        Graph::new_directed_with_edge_data()
+    }
+}
+
+impl<T: Clone + PartialEq + Eq + Hash, E> Graph<T, E> {
+    /// Like `new`, but for a graph carrying data of type `E` on each edge.
+    pub fn with_edge_data() -> Graph<T, E> {
         Graph {
             nodes: vec![],
-            edges: HashSet::new(),
+            edges: BTreeMap::new(),
             idmap: HashMap::new(),
+            directed: false,
+            outgoing: vec![],
+            incoming: vec![],
+            #[cfg(feature = "uuid")]
+            uuidmap: HashMap::new(),
+        }
+    }
+
+    /// Like `new_directed`, but for a graph carrying data of type `E` on
+    /// each edge.
+    pub fn new_directed_with_edge_data() -> Graph<T, E> {
+        Graph {
+            directed: true,
+            ..Graph::with_edge_data()
         }
     }
 
-    pub fn add_node(&mut self, new_node: Node<T>) -> Uuid {
-        let output = new_node.id;
-        let value = new_node.clone().value;
+    pub fn is_directed(&self) -> bool {
+        self.directed
+    }
+
+    pub fn add_node(&mut self, mut new_node: Node<T>) -> NodeId {
+        let output = NodeId(self.nodes.len());
+        new_node.id = output;
+        #[cfg(feature = "uuid")]
+        self.uuidmap.insert(new_node.uuid, output);
+        self.idmap.insert(new_node.value.clone(), output);
         self.nodes.push(new_node);
-        self.idmap.insert(value, output);
+        self.outgoing.push(vec![]);
+        self.incoming.push(vec![]);
         output
     }
 
-    pub fn add_node_with_value(&mut self, value: T) -> Uuid {
-        let node = Node::new(value.clone());
-        let output = node.id;
-        self.nodes.push(node);
-        self.idmap.insert(value, output);
-        output
+    pub fn add_node_with_value(&mut self, value: T) -> NodeId {
+        self.add_node(Node::new(value))
     }
 
-    pub fn get_node_ids(&self) -> Vec<Uuid> {
-        let mut ids = vec![];
-        for node in &self.nodes {
-            ids.push(node.id);
+    /// Id of the node with `value`, adding one if there isn't one yet.
+    pub fn get_or_add_node(&mut self, value: T) -> NodeId {
+        match self.get_id_from_value(&value) {
+            Some(id) => id,
+            None => self.add_node_with_value(value),
         }
-        ids
     }
 
-    pub fn get_id_from_value(&self, value: &T) -> Option<Uuid> {
+    pub fn get_node_ids(&self) -> Vec<NodeId> {
+        (0..self.nodes.len()).map(NodeId).collect()
+    }
+
+    pub fn get_id_from_value(&self, value: &T) -> Option<NodeId> {
         self.idmap.get(value).copied()
     }
 
+    #[cfg(feature = "uuid")]
+    pub fn get_id_from_uuid(&self, uuid: Uuid) -> Option<NodeId> {
+        self.uuidmap.get(&uuid).copied()
+    }
+
     pub fn get_node_from_value(&self, value: &T) -> Option<&Node<T>> {
         if let Some(id) = self.idmap.get(value) {
             self.get_node_from_id(*id)
@@ -75,190 +166,3439 @@ impl<T: Clone + PartialEq + Eq + Hash> Graph<T> {
         }
     }
 
-    pub fn get_node_from_id(&self, id: Uuid) -> Option<&Node<T>> {
-        for node in &self.nodes {
-            if node.id == id {
-                return Some(node);
-            }
-        }
-        None
+    pub fn get_node_from_id(&self, id: NodeId) -> Option<&Node<T>> {
+        self.nodes.get(id.0)
     }
 
-    pub fn get_node_from_id_mut(&mut self, id: Uuid) -> Option<&mut Node<T>> {
-        for node in &mut self.nodes {
-            if node.id == id {
-                return Some(node);
-            }
-        }
-        None
+    pub fn get_node_from_id_mut(&mut self, id: NodeId) -> Option<&mut Node<T>> {
+        self.nodes.get_mut(id.0)
     }
 
-    pub fn get_node_values(&self, ids: &[Uuid]) -> Vec<Option<T>> {
+    pub fn get_node_values(&self, ids: &[NodeId]) -> Vec<Option<T>> {
         ids.iter()
             .map(|x| self.get_node_from_id(*x).map(|node| node.value.clone()))
             .collect()
     }
 
-    pub fn get_node_edges(&self, id: Uuid) -> HashSet<(Uuid, Uuid)> {
+    /// Every edge starting or ending at `id`, in the direction it was added.
+    pub fn get_node_edges(&self, id: NodeId) -> HashSet<(NodeId, NodeId)> {
         let mut output = HashSet::new();
-        for edge in &self.edges {
-            if edge.0 == id || edge.1 == id {
-                output.insert(*edge);
-            }
+        for n in self.lookup(&self.outgoing, id) {
+            output.insert((id, n));
+        }
+        for n in self.lookup(&self.incoming, id) {
+            output.insert((n, id));
         }
         output
     }
 
-    pub fn get_node_neighbors(&self, id: Uuid) -> HashSet<Uuid> {
-        let edges = self.get_node_edges(id);
-        let mut output = HashSet::new();
-        for edge in edges {
-            if edge.0 == id {
-                output.insert(edge.1);
-            } else {
-                output.insert(edge.0);
+    pub fn get_node_neighbors(&self, id: NodeId) -> HashSet<NodeId> {
+        self.neighbors(id).collect()
+    }
+
+    fn lookup<'a>(
+        &'a self,
+        lists: &'a [Vec<NodeId>],
+        id: NodeId,
+    ) -> impl Iterator<Item = NodeId> + 'a {
+        lists
+            .get(id.0)
+            .map_or(&[][..], |list| &list[..])
+            .iter()
+            .copied()
+    }
+
+    /// Iterate over the nodes sharing an edge with `id` in either direction,
+    /// without collecting them first. Each neighbor is visited once.
+    pub fn neighbors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        // A self loop shows up in both lists
+        let outgoing = self.lookup(&self.outgoing, id);
+        let incoming = self.lookup(&self.incoming, id).filter(move |&n| n != id);
+        let incoming =
+            incoming.filter(move |&n| !self.directed || !self.edges.contains_key(&(id, n)));
+        outgoing.chain(incoming)
+    }
+
+    /// Nodes reachable from `id` along a single edge. For undirected graphs
+    /// this is the same as `neighbors`.
+    pub fn out_neighbors(&self, id: NodeId) -> Box<dyn Iterator<Item = NodeId> + '_> {
+        if self.directed {
+            Box::new(self.lookup(&self.outgoing, id))
+        } else {
+            Box::new(self.neighbors(id))
+        }
+    }
+
+    /// Nodes with an edge leading to `id`. For undirected graphs this is the
+    /// same as `neighbors`.
+    pub fn in_neighbors(&self, id: NodeId) -> Box<dyn Iterator<Item = NodeId> + '_> {
+        if self.directed {
+            Box::new(self.lookup(&self.incoming, id))
+        } else {
+            Box::new(self.neighbors(id))
+        }
+    }
+
+    pub fn out_degree(&self, id: NodeId) -> usize {
+        self.out_neighbors(id).count()
+    }
+
+    pub fn in_degree(&self, id: NodeId) -> usize {
+        self.in_neighbors(id).count()
+    }
+
+    /// True if there's an edge from `node1` to `node2`, or either way round
+    /// for undirected graphs.
+    pub fn has_edge(&self, node1: NodeId, node2: NodeId) -> bool {
+        self.edge_key(node1, node2).is_some()
+    }
+
+    /// The key `(node1, node2)` is stored under in `edges`, which is the
+    /// other way round if it was added that way to an undirected graph.
+    fn edge_key(&self, node1: NodeId, node2: NodeId) -> Option<(NodeId, NodeId)> {
+        if self.edges.contains_key(&(node1, node2)) {
+            Some((node1, node2))
+        } else if !self.directed && self.edges.contains_key(&(node2, node1)) {
+            Some((node2, node1))
+        } else {
+            None
+        }
+    }
+
+    /// Add an edge carrying `E::default()`, leaving it alone if it's
+    /// already there.
+    pub fn add_edge(&mut self, node1: NodeId, node2: NodeId)
+    where
+        E: Default,
+    {
+        if !self.has_edge(node1, node2) {
+            self.add_edge_with_data(node1, node2, E::default());
+        }
+    }
+
+    /// Add an edge carrying `data`, replacing the data if the edge is already
+    /// there.
+    pub fn add_edge_with_data(&mut self, node1: NodeId, node2: NodeId, data: E) {
+        if node1.0 >= self.nodes.len() || node2.0 >= self.nodes.len() {
+            panic!("Tried to add an edge to a node not in the graph");
+        }
+        if let Some(key) = self.edge_key(node1, node2) {
+            self.edges.insert(key, data);
+        } else {
+            self.edges.insert((node1, node2), data);
+            self.outgoing[node1.0].push(node2);
+            self.incoming[node2.0].push(node1);
+        }
+    }
+
+    pub fn get_edge_data(&self, node1: NodeId, node2: NodeId) -> Option<&E> {
+        self.edge_key(node1, node2).map(|key| &self.edges[&key])
+    }
+
+    pub fn get_edge_data_mut(&mut self, node1: NodeId, node2: NodeId) -> Option<&mut E> {
+        self.edge_key(node1, node2)
+            .and_then(|key| self.edges.get_mut(&key))
+    }
+
+    /// Remove the edge from `node1` to `node2`, or either way round for
+    /// undirected graphs. Returns the edge's data if there was one.
+    pub fn remove_edge(&mut self, node1: NodeId, node2: NodeId) -> Option<E> {
+        let (from, to) = self.edge_key(node1, node2)?;
+        self.outgoing[from.0].retain(|&n| n != to);
+        self.incoming[to.0].retain(|&n| n != from);
+        self.edges.remove(&(from, to))
+    }
+
+    /// Walk the graph to find every node joined to `node` by some chain of
+    /// edges, ignoring their direction, including `node` itself. Nodes are in
+    /// the order they were reached.
+    pub fn get_connected_nodes(&self, node: NodeId) -> Vec<NodeId> {
+        if node.0 >= self.nodes.len() {
+            return vec![];
+        }
+        let mut output = vec![node];
+        let mut seen = HashSet::from([node]);
+        let mut idx = 0;
+        while idx < output.len() {
+            for n in self.neighbors(output[idx]) {
+                if seen.insert(n) {
+                    output.push(n);
+                }
             }
+            idx += 1;
         }
         output
     }
 
-    pub fn add_edge(&mut self, node1: Uuid, node2: Uuid) {
-        self.edges.insert((node1, node2));
+    /// Split the graph into connected components, ignoring edge direction.
+    pub fn connected_components(&self) -> Components<NodeId> {
+        let mut sets = UnionFind::new(self.nodes.len());
+        for &(a, b) in self.edges.keys() {
+            sets.union(a.0, b.0);
+        }
+        Components::from_sets(self.get_node_ids(), &mut sets)
     }
+}
 
-    // TODO: Add a method to `get_connected_nodes(&self, node: Uuid) -> Vec<Uuid>
-    // which will walk the graph and get all nodes connected to the input `node`.
+/// A line of input that couldn't be parsed into part of a graph.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// Line number, counting from 1.
+    pub line: usize,
+    pub message: String,
 }
 
-pub trait Connected {
-    type Item;
-    fn get_neighbors(&self, node: &Self::Item) -> Vec<Self::Item>;
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
 }
 
-pub fn shortest_path<T, U>(start: &T, end: &T, graph: &U) -> Option<Vec<T>>
-where
-    T: Clone + Eq + Hash,
-    U: Connected<Item = T>,
-{
-    let mut paths: HashMap<T, Vec<T>> = HashMap::new();
-    let mut investigate: HashSet<T> = HashSet::new();
-    let mut visited: HashSet<T> = HashSet::new();
-
-    investigate.insert(end.clone());
-    paths.insert(end.clone(), vec![end.clone()]);
-    loop {
-        let mut investigate_next: HashSet<T> = HashSet::new();
-
-        // Iterate over nodes to investigate
-        for trial in &investigate {
-            let neighbors = graph.get_neighbors(&trial);
-
-            // Iterate over neighbors of trial node
-            let curr_path = paths.get(trial).unwrap().clone();
-            for n in neighbors {
-                if let Some(p) = paths.get_mut(&n) {
-                    // If there exists a path to n already, see if this one is
-                    // shorter, insert if it is.
-                    if p.len() > curr_path.len() + 1 {
-                        let mut tpath = curr_path.clone();
-                        tpath.push(n.clone());
-                        *p = tpath;
+impl std::error::Error for ParseError {}
+
+impl<T: Clone + PartialEq + Eq + Hash, E: Default> Graph<T, E> {
+    /// Build a graph from lines with one edge each, with the two nodes split
+    /// by any of `separators`, like `a-b` or `COM)B`. Where separators
+    /// overlap, as with `-` and `->`, the longest one at a position is used.
+    /// Nodes are made from the text either side, trimmed of whitespace, and
+    /// added the first time they appear. Blank lines are skipped, and any
+    /// other line without exactly one separator is an error.
+    pub fn from_edge_list<'a>(
+        input: &'a str,
+        separators: &[&str],
+        directed: bool,
+    ) -> Result<Graph<T, E>, ParseError>
+    where
+        T: From<&'a str>,
+    {
+        let mut graph = Graph::with_edge_data();
+        graph.directed = directed;
+        for (line, a, rest) in split_lines(input, separators)? {
+            if rest.is_empty() {
+                return Err(ParseError {
+                    line,
+                    message: "missing second node".to_string(),
+                });
+            }
+            if split_once_any(rest, separators).is_some() {
+                return Err(ParseError {
+                    line,
+                    message: "more than one separator".to_string(),
+                });
+            }
+            let a = graph.get_or_add_node(a.into());
+            let b = graph.get_or_add_node(rest.into());
+            graph.add_edge(a, b);
+        }
+        Ok(graph)
+    }
+
+    /// Build a graph from lines each giving a node then, after `separator`,
+    /// the nodes it has edges to, split up by any of `list_separators`, like
+    /// `a: b c d` or `AA -> BB, CC`. Empty items between separators are
+    /// ignored, so `&[',', ' ']` handles `, `. A node can be listed with no
+    /// edges. Nodes are added the first time they appear, and blank lines
+    /// are skipped.
+    pub fn from_adjacency_list<'a>(
+        input: &'a str,
+        separator: &str,
+        list_separators: &[char],
+        directed: bool,
+    ) -> Result<Graph<T, E>, ParseError>
+    where
+        T: From<&'a str>,
+    {
+        let mut graph = Graph::with_edge_data();
+        graph.directed = directed;
+        for (_, a, rest) in split_lines(input, &[separator])? {
+            let a = graph.get_or_add_node(a.into());
+            for b in rest.split(list_separators).filter(|b| !b.is_empty()) {
+                let b = graph.get_or_add_node(b.into());
+                graph.add_edge(a, b);
+            }
+        }
+        Ok(graph)
+    }
+}
+
+/// Split each non blank line of `input` at the first of `separators`, giving
+/// the line number and the trimmed text either side.
+fn split_lines<'a>(
+    input: &'a str,
+    separators: &[&str],
+) -> Result<Vec<(usize, &'a str, &'a str)>, ParseError> {
+    let mut output = vec![];
+    for (i, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Some((a, rest)) = split_once_any(line, separators) else {
+            return Err(ParseError {
+                line: i + 1,
+                message: format!("no separator from {:?}", separators),
+            });
+        };
+        if a.trim().is_empty() {
+            return Err(ParseError {
+                line: i + 1,
+                message: "missing first node".to_string(),
+            });
+        }
+        output.push((i + 1, a.trim(), rest.trim()));
+    }
+    Ok(output)
+}
+
+/// Split `line` around the earliest of `separators`, preferring the longest
+/// where several start at the same place.
+fn split_once_any<'a>(line: &'a str, separators: &[&str]) -> Option<(&'a str, &'a str)> {
+    separators
+        .iter()
+        .filter_map(|sep| line.find(sep).map(|i| (i, sep.len())))
+        .min_by_key(|&(i, len)| (i, Reverse(len)))
+        .map(|(i, len)| (&line[..i], &line[i + len..]))
+}
+
+impl<T: Clone + PartialEq + Eq + Hash + fmt::Display, E> Graph<T, E> {
+    /// Write the graph in Graphviz DOT format, labelling nodes with their
+    /// values. The nodes of `path`, and the edges between consecutive ones,
+    /// are highlighted in red.
+    pub fn write_dot<W: io::Write>(&self, w: &mut W, path: &[NodeId]) -> io::Result<()> {
+        self.diagram(path, |_| None).write(w, Format::Dot)
+    }
+
+    /// Like `write_dot`, but with edges labelled with their data.
+    pub fn write_dot_weighted<W: io::Write>(&self, w: &mut W, path: &[NodeId]) -> io::Result<()>
+    where
+        E: fmt::Display,
+    {
+        self.diagram(path, |e| Some(e.to_string()))
+            .write(w, Format::Dot)
+    }
+
+    /// Write the graph as a Mermaid flowchart, highlighting `path` like
+    /// `write_dot`.
+    pub fn write_mermaid<W: io::Write>(&self, w: &mut W, path: &[NodeId]) -> io::Result<()> {
+        self.diagram(path, |_| None).write(w, Format::Mermaid)
+    }
+
+    /// Like `write_mermaid`, but with edges labelled with their data.
+    pub fn write_mermaid_weighted<W: io::Write>(&self, w: &mut W, path: &[NodeId]) -> io::Result<()>
+    where
+        E: fmt::Display,
+    {
+        self.diagram(path, |e| Some(e.to_string()))
+            .write(w, Format::Mermaid)
+    }
+
+    fn diagram<F: Fn(&E) -> Option<String>>(&self, path: &[NodeId], label: F) -> Diagram {
+        Diagram {
+            directed: self.directed,
+            nodes: self.nodes.iter().map(|n| n.value.to_string()).collect(),
+            edges: self
+                .edges
+                .iter()
+                .map(|(&(a, b), e)| (a.0, b.0, label(e)))
+                .collect(),
+            path: path.iter().map(|n| n.0).collect(),
+        }
+    }
+}
+
+impl<T: Clone + PartialEq + Eq + Hash, E> Connected for Graph<T, E> {
+    type Item = NodeId;
+    fn get_neighbors(&self, node: &NodeId) -> Vec<NodeId> {
+        self.out_neighbors(*node).collect()
+    }
+}
+
+/// Uses the edge data as the cost of stepping along an edge.
+impl<T: Clone + PartialEq + Eq + Hash, E: Copy> WeightedConnected for Graph<T, E> {
+    type Item = NodeId;
+    type Cost = E;
+    fn get_weighted_neighbors(&self, node: &NodeId) -> Vec<(NodeId, E)> {
+        self.out_neighbors(*node)
+            .map(|n| (n, *self.get_edge_data(*node, n).unwrap()))
+            .collect()
+    }
+}
+
+/// One step of a `Schedule`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ScheduledStep {
+    pub node: NodeId,
+    pub worker: usize,
+    pub start: u64,
+    pub end: u64,
+}
+
+/// Result of `Graph::schedule`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Schedule {
+    /// Every step, in the order they were started.
+    pub steps: Vec<ScheduledStep>,
+    /// When the last step finished.
+    pub total_time: u64,
+}
+
+impl<T: Clone + PartialEq + Eq + Hash, E> Graph<T, E> {
+    /// Order the nodes so that every edge leads from an earlier node to a
+    /// later one, using Kahn's algorithm. When several nodes could go next
+    /// the smallest value goes first. If the graph has a cycle there is no
+    /// such order, and the cycle is returned as the error instead.
+    pub fn topological_sort(&self) -> Result<Vec<NodeId>, Vec<NodeId>>
+    where
+        T: Ord,
+    {
+        self.topological_sort_by(|a, b| a.cmp(b))
+    }
+
+    /// Like `topological_sort`, but ties go to the node whose value comes
+    /// first according to `cmp`.
+    pub fn topological_sort_by<F>(&self, cmp: F) -> Result<Vec<NodeId>, Vec<NodeId>>
+    where
+        F: Fn(&T, &T) -> Ordering,
+    {
+        let schedule = self.schedule_by(1, |_| 1, cmp)?;
+        Ok(schedule.steps.iter().map(|step| step.node).collect())
+    }
+
+    /// Simulate `workers` workers carrying out every node as a step, where
+    /// a step takes `duration(value)` time and can't start until every step
+    /// with an edge leading to it has finished. Free workers take the
+    /// available step with the smallest value. Errors with a cycle if the
+    /// steps can't all be done.
+    pub fn schedule<D>(&self, workers: usize, duration: D) -> Result<Schedule, Vec<NodeId>>
+    where
+        T: Ord,
+        D: Fn(&T) -> u64,
+    {
+        self.schedule_by(workers, duration, |a, b| a.cmp(b))
+    }
+
+    /// Like `schedule`, but available steps are taken in the order given by
+    /// `cmp`. Panics if there are no workers.
+    pub fn schedule_by<D, F>(
+        &self,
+        workers: usize,
+        duration: D,
+        cmp: F,
+    ) -> Result<Schedule, Vec<NodeId>>
+    where
+        D: Fn(&T) -> u64,
+        F: Fn(&T, &T) -> Ordering,
+    {
+        assert!(workers > 0, "Can't schedule steps with no workers");
+        let ids = self.get_node_ids();
+        let mut remaining: Vec<usize> = ids.iter().map(|&id| self.in_degree(id)).collect();
+
+        // Available steps, kept sorted so the next to start is at the front
+        let mut ready: Vec<NodeId> = vec![];
+        let insert = |ready: &mut Vec<NodeId>, id: NodeId| {
+            let value = &self.nodes[id.0].value;
+            let pos = ready.partition_point(|other| {
+                cmp(&self.nodes[other.0].value, value) != Ordering::Greater
+            });
+            ready.insert(pos, id);
+        };
+        for &id in &ids {
+            if remaining[id.0] == 0 {
+                insert(&mut ready, id);
+            }
+        }
+
+        let mut steps = vec![];
+        let mut busy: Vec<Option<ScheduledStep>> = vec![None; workers];
+        let mut time = 0;
+        loop {
+            for (worker, slot) in busy.iter_mut().enumerate() {
+                if slot.is_none() && !ready.is_empty() {
+                    let node = ready.remove(0);
+                    let end = time + duration(&self.nodes[node.0].value);
+                    let step = ScheduledStep {
+                        node,
+                        worker,
+                        start: time,
+                        end,
+                    };
+                    steps.push(step);
+                    *slot = Some(step);
+                }
+            }
+
+            // Jump ahead to the next step finishing
+            let Some(next) = busy.iter().flatten().map(|step| step.end).min() else {
+                break;
+            };
+            time = next;
+            for slot in busy.iter_mut() {
+                if let Some(step) = slot.filter(|step| step.end == time) {
+                    *slot = None;
+                    for n in self.out_neighbors(step.node) {
+                        remaining[n.0] -= 1;
+                        if remaining[n.0] == 0 {
+                            insert(&mut ready, n);
+                        }
                     }
-                } else {
-                    // If there is no path to n already, use this one
-                    let mut tpath = curr_path.clone();
-                    tpath.push(n.clone());
-                    paths.insert(n.clone(), tpath);
                 }
+            }
+        }
+
+        if steps.len() < ids.len() {
+            return Err(self
+                .find_cycle()
+                .expect("Unscheduled steps without a cycle"));
+        }
+        Ok(Schedule {
+            steps,
+            total_time: time,
+        })
+    }
 
-                // Visit n if we haven't already
-                if !visited.contains(&n) {
-                    investigate_next.insert(n);
+    /// Find a cycle, following edges in their direction for directed
+    /// graphs. The cycle is listed in order without repeating the first
+    /// node. Undirected graphs count any edge as a cycle of 2.
+    pub fn find_cycle(&self) -> Option<Vec<NodeId>> {
+        // 0 is unvisited, 1 is on the current path, 2 is finished
+        let mut state = vec![0_u8; self.nodes.len()];
+        for root in self.get_node_ids() {
+            if state[root.0] != 0 {
+                continue;
+            }
+            let mut path: Vec<(NodeId, Vec<NodeId>)> = vec![];
+            state[root.0] = 1;
+            path.push((root, self.out_neighbors(root).collect()));
+            while let Some((node, todo)) = path.last_mut() {
+                let node = *node;
+                match todo.pop() {
+                    Some(n) if state[n.0] == 1 => {
+                        let start = path.iter().position(|(p, _)| *p == n).unwrap();
+                        return Some(path[start..].iter().map(|(p, _)| *p).collect());
+                    }
+                    Some(n) if state[n.0] == 0 => {
+                        state[n.0] = 1;
+                        path.push((n, self.out_neighbors(n).collect()));
+                    }
+                    Some(_) => {}
+                    None => {
+                        state[node.0] = 2;
+                        path.pop();
+                    }
                 }
             }
+        }
+        None
+    }
 
-            // Put trial in visited if it isn't already there
-            visited.insert(trial.clone());
+    /// Strongly connected components, groups of nodes that can all reach
+    /// each other by following edges, found with Tarjan's algorithm. The
+    /// components are in topological order, so no edge leads from a
+    /// component to an earlier one, and the nodes in each are sorted.
+    pub fn strongly_connected_components(&self) -> Components<NodeId> {
+        let unvisited = usize::MAX;
+        let mut index = vec![unvisited; self.nodes.len()];
+        let mut low = vec![0; self.nodes.len()];
+        let mut on_stack = vec![false; self.nodes.len()];
+        let mut stack: Vec<NodeId> = vec![];
+        let mut components: Vec<Vec<NodeId>> = vec![];
+        let mut counter = 0;
+        for root in self.get_node_ids() {
+            if index[root.0] != unvisited {
+                continue;
+            }
+            let mut path: Vec<(NodeId, Vec<NodeId>)> = vec![];
+            let mut next = Some(root);
+            loop {
+                if let Some(n) = next.take() {
+                    index[n.0] = counter;
+                    low[n.0] = counter;
+                    counter += 1;
+                    stack.push(n);
+                    on_stack[n.0] = true;
+                    path.push((n, self.out_neighbors(n).collect()));
+                }
+                let Some((node, todo)) = path.last_mut() else {
+                    break;
+                };
+                let node = *node;
+                match todo.pop() {
+                    Some(n) if index[n.0] == unvisited => next = Some(n),
+                    Some(n) => {
+                        if on_stack[n.0] {
+                            low[node.0] = low[node.0].min(index[n.0]);
+                        }
+                    }
+                    None => {
+                        path.pop();
+                        if let Some((parent, _)) = path.last() {
+                            low[parent.0] = low[parent.0].min(low[node.0]);
+                        }
+                        if low[node.0] == index[node.0] {
+                            let mut component = vec![];
+                            while let Some(n) = stack.pop() {
+                                on_stack[n.0] = false;
+                                component.push(n);
+                                if n == node {
+                                    break;
+                                }
+                            }
+                            component.sort();
+                            components.push(component);
+                        }
+                    }
+                }
+            }
         }
 
-        if investigate_next.is_empty() {
-            break;
-        } else {
-            investigate = investigate_next;
+        // Tarjan's algorithm finishes components in reverse topological
+        // order
+        components.reverse();
+        let membership = components
+            .iter()
+            .enumerate()
+            .flat_map(|(i, c)| c.iter().map(move |&n| (n, i)))
+            .collect();
+        Components {
+            components,
+            membership,
         }
     }
 
-    paths.get(&start).cloned()
+    /// The directed acyclic graph made by merging each strongly connected
+    /// component into a single node, whose value is the nodes in it. Node
+    /// `NodeId(i)` is component `i` of `strongly_connected_components`, so
+    /// the nodes are in topological order.
+    pub fn condensation(&self) -> Graph<Vec<NodeId>> {
+        let components = self.strongly_connected_components();
+        let mut output = Graph::new_directed();
+        for component in &components.components {
+            output.add_node_with_value(component.clone());
+        }
+        // The ends of an undirected edge are always in the same component
+        for &(a, b) in self.edges.keys() {
+            let (a, b) = (components.membership[&a], components.membership[&b]);
+            if a != b && !output.has_edge(NodeId(a), NodeId(b)) {
+                output.add_edge(NodeId(a), NodeId(b));
+            }
+        }
+        output
+    }
+
+    /// Every simple cycle that passes through `node`, following edges in
+    /// their direction. Each starts at `node` and doesn't repeat it at the
+    /// end. Meant for directed graphs, as in an undirected one each cycle
+    /// is found both ways round, and every edge is a cycle of length 2.
+    /// Only nodes in the same strongly connected component as `node` are
+    /// searched, but the number of cycles can still grow exponentially.
+    pub fn cycles_through(&self, node: NodeId) -> Vec<Vec<NodeId>> {
+        let sccs = self.strongly_connected_components();
+        let component = sccs.membership[&node];
+        let mut output = vec![];
+        let mut on_path = vec![false; self.nodes.len()];
+        on_path[node.0] = true;
+        let mut path: Vec<(NodeId, Vec<NodeId>)> = vec![(node, self.out_neighbors(node).collect())];
+        while let Some((_, todo)) = path.last_mut() {
+            match todo.pop() {
+                Some(n) if n == node => output.push(path.iter().map(|(p, _)| *p).collect()),
+                Some(n) if !on_path[n.0] && sccs.membership[&n] == component => {
+                    on_path[n.0] = true;
+                    path.push((n, self.out_neighbors(n).collect()));
+                }
+                Some(_) => {}
+                None => {
+                    let (n, _) = path.pop().unwrap();
+                    on_path[n.0] = false;
+                }
+            }
+        }
+        output
+    }
+
+    /// The distinct lengths of the cycles through `node`, in increasing
+    /// order. Combine with `sync_period` to find when they all line up.
+    pub fn cycle_lengths(&self, node: NodeId) -> Vec<usize> {
+        let mut lengths: Vec<usize> = self.cycles_through(node).iter().map(|c| c.len()).collect();
+        lengths.sort();
+        lengths.dedup();
+        lengths
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+impl<T: Clone + PartialEq + Eq + Hash, E> Graph<T, E> {
+    /// Neighbor sets by node index, ignoring edge direction and self loops.
+    fn neighbor_sets(&self) -> Vec<HashSet<usize>> {
+        self.get_node_ids()
+            .into_iter()
+            .map(|id| {
+                self.neighbors(id)
+                    .filter(|&n| n != id)
+                    .map(|n| n.0)
+                    .collect()
+            })
+            .collect()
+    }
 
-    #[test]
-    fn nodes() {
-        let n1 = Node::new(12);
-        assert_eq!(n1.value, 12);
+    /// Every maximal clique, a set of nodes all joined to one another that
+    /// can't be grown any further, using Bron–Kerbosch with pivoting. Edge
+    /// direction is ignored. Each clique is sorted, as is the list.
+    pub fn maximal_cliques(&self) -> Vec<Vec<NodeId>> {
+        let adjacent = self.neighbor_sets();
+        let mut output = vec![];
+        bron_kerbosch(
+            &adjacent,
+            &mut vec![],
+            (0..self.nodes.len()).collect(),
+            HashSet::new(),
+            &mut output,
+        );
+        let mut output: Vec<Vec<NodeId>> = output
+            .into_iter()
+            .map(|mut clique| {
+                clique.sort();
+                clique.into_iter().map(NodeId).collect()
+            })
+            .collect();
+        output.sort();
+        output
     }
 
-    #[test]
-    fn graphs() {
-        let mut g = Graph::<i64>::new();
-        let id0 = g.add_node(Node::new(12));
-        assert_eq!(g.nodes[0].value, 12);
+    /// The largest clique, taking the first in `maximal_cliques` order if
+    /// there's a tie.
+    pub fn maximum_clique(&self) -> Vec<NodeId> {
+        let mut best = vec![];
+        for clique in self.maximal_cliques() {
+            if clique.len() > best.len() {
+                best = clique;
+            }
+        }
+        best
+    }
 
-        let id1 = g.add_node_with_value(15);
-        assert_eq!(g.nodes[1].value, 15);
+    /// Every set of 3 nodes all joined to each other whose values pass
+    /// `filter`, ignoring edge direction. Each triangle is sorted, as is
+    /// the list.
+    pub fn triangles<F>(&self, filter: F) -> Vec<[NodeId; 3]>
+    where
+        F: Fn(&T, &T, &T) -> bool,
+    {
+        let adjacent = self.neighbor_sets();
+        let mut output = vec![];
+        for a in 0..adjacent.len() {
+            // Only look at higher numbered neighbors so each triangle is
+            // found once
+            let mut higher: Vec<usize> = adjacent[a].iter().copied().filter(|&b| b > a).collect();
+            higher.sort();
+            for (i, &b) in higher.iter().enumerate() {
+                for &c in &higher[i + 1..] {
+                    let values = (
+                        &self.nodes[a].value,
+                        &self.nodes[b].value,
+                        &self.nodes[c].value,
+                    );
+                    if adjacent[b].contains(&c) && filter(values.0, values.1, values.2) {
+                        output.push([NodeId(a), NodeId(b), NodeId(c)]);
+                    }
+                }
+            }
+        }
+        output
+    }
 
-        let id2 = g.add_node_with_value(1);
-        let id3 = g.add_node_with_value(2);
-        let id4 = g.add_node_with_value(3);
+    /// Color the nodes so no two neighbors share a color, visiting nodes in
+    /// id order and giving each the lowest color its neighbors aren't using.
+    /// Returns the color of each node, indexed by `NodeId`.
+    pub fn greedy_coloring(&self) -> Vec<usize> {
+        let adjacent = self.neighbor_sets();
+        let mut colors: Vec<Option<usize>> = vec![None; adjacent.len()];
+        for node in 0..adjacent.len() {
+            colors[node] = Some(lowest_free_color(&adjacent[node], &colors));
+        }
+        colors.into_iter().flatten().collect()
+    }
 
-        // Grab references to a couple Nodes
-        let node_ids = g.get_node_ids();
+    /// Color the nodes like `greedy_coloring`, but using DSatur to pick the
+    /// order: next is the node whose neighbors already use the most
+    /// different colors, then the one with the most neighbors, then the
+    /// lowest id. This usually needs fewer colors.
+    pub fn dsatur_coloring(&self) -> Vec<usize> {
+        let adjacent = self.neighbor_sets();
+        let mut colors: Vec<Option<usize>> = vec![None; adjacent.len()];
+        let mut saturation: Vec<HashSet<usize>> = vec![HashSet::new(); adjacent.len()];
+        for _ in 0..adjacent.len() {
+            let node = (0..adjacent.len())
+                .filter(|&n| colors[n].is_none())
+                .max_by_key(|&n| (saturation[n].len(), adjacent[n].len(), Reverse(n)))
+                .unwrap();
+            let color = lowest_free_color(&adjacent[node], &colors);
+            colors[node] = Some(color);
+            for &n in &adjacent[node] {
+                saturation[n].insert(color);
+            }
+        }
+        colors.into_iter().flatten().collect()
+    }
+}
 
-        g.add_edge(id0, id1);
-        g.add_edge(id0, id2);
-        let neighbors = g.get_node_neighbors(id0);
-        let mut testset = HashSet::new();
-        testset.insert(id1);
-        testset.insert(id2);
-        assert_eq!(neighbors, testset);
+fn lowest_free_color(neighbors: &HashSet<usize>, colors: &[Option<usize>]) -> usize {
+    let used: HashSet<usize> = neighbors.iter().filter_map(|&n| colors[n]).collect();
+    (0..).find(|c| !used.contains(c)).unwrap()
+}
+
+fn bron_kerbosch(
+    adjacent: &[HashSet<usize>],
+    clique: &mut Vec<usize>,
+    mut candidates: HashSet<usize>,
+    mut excluded: HashSet<usize>,
+    output: &mut Vec<Vec<usize>>,
+) {
+    if candidates.is_empty() {
+        if excluded.is_empty() {
+            output.push(clique.clone());
+        }
+        return;
     }
 
-    #[test]
-    fn path_finding() {
-        impl Connected for HashSet<(usize, usize)> {
-            type Item = (usize, usize);
-            fn get_neighbors(&self, node: &Self::Item) -> Vec<Self::Item> {
-                let mut candidates = vec![];
-                candidates.push((node.0, node.1 + 1));
-                candidates.push((node.0 + 1, node.1));
-                if node.1 > 0 {
-                    candidates.push((node.0, node.1 - 1));
-                }
-                if node.0 > 0 {
-                    candidates.push((node.0 - 1, node.1));
-                }
-
-                let mut output = vec![];
-                for candidate in candidates {
-                    if self.contains(&candidate) {
-                        output.push(candidate);
-                    }
+    // Anything joined to the pivot will be found from the pivot's branch
+    let pivot = *candidates
+        .union(&excluded)
+        .max_by_key(|&&u| candidates.intersection(&adjacent[u]).count())
+        .unwrap();
+    let mut branches: Vec<usize> = candidates.difference(&adjacent[pivot]).copied().collect();
+    branches.sort();
+
+    for v in branches {
+        clique.push(v);
+        bron_kerbosch(
+            adjacent,
+            clique,
+            candidates.intersection(&adjacent[v]).copied().collect(),
+            excluded.intersection(&adjacent[v]).copied().collect(),
+            output,
+        );
+        clique.pop();
+        candidates.remove(&v);
+        excluded.insert(v);
+    }
+}
+
+/// A split of a graph's nodes in two, see `Graph::max_flow` and
+/// `Graph::min_cut`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cut<T: Clone + Eq + Hash, C> {
+    /// Total capacity of the edges crossing the cut.
+    pub value: C,
+    pub side_a: HashSet<T>,
+    pub side_b: HashSet<T>,
+    /// Edges with an end on each side, sorted. For a `max_flow` cut of a
+    /// directed graph, only those leading from `side_a` to `side_b`.
+    pub edges: Vec<(NodeId, NodeId)>,
+}
+
+/// Result of `Graph::max_flow`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MaxFlow<T: Clone + Eq + Hash, C> {
+    pub value: C,
+    /// Flow along each edge that carries any, keyed in the direction it
+    /// flows.
+    pub flows: BTreeMap<(NodeId, NodeId), C>,
+    /// A minimum cut with the source in `side_a` and the sink in `side_b`.
+    pub cut: Cut<T, C>,
+}
+
+/// Arc of the residual network used by `Graph::max_flow`.
+struct Arc<C> {
+    to: usize,
+    capacity: C,
+    /// Index of the paired arc going the other way in `to`'s list.
+    rev: usize,
+}
+
+impl<T: Clone + PartialEq + Eq + Hash, E> Graph<T, E> {
+    /// Split the nodes into `side_a` and the rest, and collect the edges
+    /// between them. If `directed`, only edges leading from `side_a` count.
+    fn make_cut<C>(&self, value: C, side_a: &HashSet<usize>, directed: bool) -> Cut<T, C> {
+        let mut cut = Cut {
+            value,
+            side_a: HashSet::new(),
+            side_b: HashSet::new(),
+            edges: vec![],
+        };
+        for (idx, node) in self.nodes.iter().enumerate() {
+            if side_a.contains(&idx) {
+                cut.side_a.insert(node.value.clone());
+            } else {
+                cut.side_b.insert(node.value.clone());
+            }
+        }
+        cut.edges = self
+            .edges
+            .keys()
+            .filter(|(a, b)| {
+                let (in_a, in_b) = (side_a.contains(&a.0), side_a.contains(&b.0));
+                if directed {
+                    in_a && !in_b
+                } else {
+                    in_a != in_b
+                }
+            })
+            .copied()
+            .collect();
+        cut
+    }
+
+    /// Maximum flow from `source` to `sink` using Dinic's algorithm, with
+    /// each edge able to carry `capacity(data)`. Edges of undirected graphs
+    /// can carry their capacity either way. Also gives the minimum cut
+    /// separating `source` from `sink`. Panics if they're the same node.
+    pub fn max_flow<C, F>(&self, source: NodeId, sink: NodeId, capacity: F) -> MaxFlow<T, C>
+    where
+        C: Copy + Ord + Zero + Sub<Output = C>,
+        F: Fn(&E) -> C,
+    {
+        assert!(
+            source != sink,
+            "max_flow source and sink must be different nodes"
+        );
+        let n = self.nodes.len();
+        let mut arcs: Vec<Vec<Arc<C>>> = (0..n).map(|_| vec![]).collect();
+        let mut edge_arcs = vec![];
+        for (&(a, b), data) in &self.edges {
+            let cap = capacity(data);
+            let back = if self.directed { C::zero() } else { cap };
+            let (ia, ib) = (arcs[a.0].len(), arcs[b.0].len() + usize::from(a == b));
+            arcs[a.0].push(Arc {
+                to: b.0,
+                capacity: cap,
+                rev: ib,
+            });
+            arcs[b.0].push(Arc {
+                to: a.0,
+                capacity: back,
+                rev: ia,
+            });
+            edge_arcs.push((a, b, cap, ia));
+        }
+
+        let mut value = C::zero();
+        while let Some(level) = flow_levels(&arcs, source.0, sink.0) {
+            let mut next = vec![0; n];
+            loop {
+                let pushed = push_flow(&mut arcs, &level, &mut next, source.0, sink.0, None);
+                if pushed == C::zero() {
+                    break;
                 }
-                output
+                value = value + pushed;
             }
         }
-        let mut graph: HashSet<(usize, usize)> = HashSet::new();
-        for i in 0..20 {
-            for j in 0..20 {
-                graph.insert((i, j));
+
+        let mut flows = BTreeMap::new();
+        for (a, b, cap, ia) in edge_arcs {
+            let left = arcs[a.0][ia].capacity;
+            if left < cap {
+                flows.insert((a, b), cap - left);
+            } else if cap < left {
+                flows.insert((b, a), left - cap);
             }
         }
-        let path = shortest_path(&(12, 16), &(15, 7), &graph).unwrap();
-        assert_eq!(path.len() - 1, 12);
-        let path = shortest_path(&(2, 16), &(15, 17), &graph).unwrap();
-        assert_eq!(path.len() - 1, 14);
+
+        // The source side is whatever can still be reached in the residual
+        let mut side_a = HashSet::from([source.0]);
+        let mut stack = vec![source.0];
+        while let Some(v) = stack.pop() {
+            for arc in &arcs[v] {
+                if arc.capacity > C::zero() && side_a.insert(arc.to) {
+                    stack.push(arc.to);
+                }
+            }
+        }
+
+        MaxFlow {
+            value,
+            flows,
+            cut: self.make_cut(value, &side_a, self.directed),
+        }
+    }
+
+    /// Global minimum cut using Stoer–Wagner: the split of the nodes into
+    /// two non-empty groups with the least total `weight(data)` on the
+    /// edges between them. Edge direction is ignored. Returns `None` for
+    /// graphs with fewer than 2 nodes.
+    pub fn min_cut<C, F>(&self, weight: F) -> Option<Cut<T, C>>
+    where
+        C: Copy + Ord + Zero,
+        F: Fn(&E) -> C,
+    {
+        let n = self.nodes.len();
+        if n < 2 {
+            return None;
+        }
+        let mut adjacent: Vec<HashMap<usize, C>> = vec![HashMap::new(); n];
+        for (&(a, b), data) in &self.edges {
+            if a != b {
+                let w = weight(data);
+                let total = adjacent[a.0].get(&b.0).copied().unwrap_or_else(C::zero) + w;
+                adjacent[a.0].insert(b.0, total);
+                adjacent[b.0].insert(a.0, total);
+            }
+        }
+        let mut groups: Vec<Vec<usize>> = (0..n).map(|v| vec![v]).collect();
+        let mut active: Vec<usize> = (0..n).collect();
+        let mut best: Option<(C, Vec<usize>)> = None;
+
+        while active.len() > 1 {
+            // Maximum adjacency ordering: keep adding the node most tightly
+            // joined to those already added.
+            let mut added = vec![false; n];
+            let mut joined = vec![C::zero(); n];
+            let mut heap: BinaryHeap<(C, Reverse<usize>)> =
+                active.iter().map(|&v| (C::zero(), Reverse(v))).collect();
+            let (mut prev, mut last) = (usize::MAX, usize::MAX);
+            let mut last_weight = C::zero();
+            while let Some((w, Reverse(v))) = heap.pop() {
+                if added[v] {
+                    continue;
+                }
+                added[v] = true;
+                prev = last;
+                last = v;
+                last_weight = w;
+                for (&u, &c) in &adjacent[v] {
+                    if !added[u] {
+                        joined[u] = joined[u] + c;
+                        heap.push((joined[u], Reverse(u)));
+                    }
+                }
+            }
+
+            // Cutting off the last node added is the best cut of this phase
+            if best.as_ref().is_none_or(|(value, _)| last_weight < *value) {
+                best = Some((last_weight, groups[last].clone()));
+            }
+
+            // Merge the last two nodes added
+            let merged = std::mem::take(&mut adjacent[last]);
+            for (u, c) in merged {
+                adjacent[u].remove(&last);
+                if u != prev {
+                    let w = adjacent[prev].get(&u).copied().unwrap_or_else(C::zero) + c;
+                    adjacent[prev].insert(u, w);
+                    adjacent[u].insert(prev, w);
+                }
+            }
+            let members = std::mem::take(&mut groups[last]);
+            groups[prev].extend(members);
+            active.retain(|&v| v != last);
+        }
+
+        let (value, side_a) = best.unwrap();
+        Some(self.make_cut(value, &side_a.into_iter().collect(), false))
+    }
+}
+
+/// Breadth first distances from `source` over arcs with capacity left, or
+/// `None` if `sink` can't be reached.
+fn flow_levels<C: Copy + Ord + Zero>(
+    arcs: &[Vec<Arc<C>>],
+    source: usize,
+    sink: usize,
+) -> Option<Vec<usize>> {
+    let mut level = vec![usize::MAX; arcs.len()];
+    level[source] = 0;
+    let mut queue = VecDeque::from([source]);
+    while let Some(v) = queue.pop_front() {
+        for arc in &arcs[v] {
+            if arc.capacity > C::zero() && level[arc.to] == usize::MAX {
+                level[arc.to] = level[v] + 1;
+                queue.push_back(arc.to);
+            }
+        }
+    }
+    if level[sink] == usize::MAX {
+        None
+    } else {
+        Some(level)
+    }
+}
+
+/// Push up to `limit` flow from `v` to `sink` along arcs going up one level
+/// at a time, or as much as the arcs allow if None, returning how much got
+/// through. `next` remembers which arcs are used up.
+fn push_flow<C>(
+    arcs: &mut [Vec<Arc<C>>],
+    level: &[usize],
+    next: &mut [usize],
+    v: usize,
+    sink: usize,
+    limit: Option<C>,
+) -> C
+where
+    C: Copy + Ord + Zero + Sub<Output = C>,
+{
+    if v == sink {
+        return limit.unwrap_or_else(C::zero);
+    }
+    while next[v] < arcs[v].len() {
+        let (to, capacity, rev) = {
+            let arc = &arcs[v][next[v]];
+            (arc.to, arc.capacity, arc.rev)
+        };
+        if capacity > C::zero() && level[to] == level[v] + 1 {
+            let limit = limit.map_or(capacity, |l| l.min(capacity));
+            let pushed = push_flow(arcs, level, next, to, sink, Some(limit));
+            if pushed > C::zero() {
+                let idx = next[v];
+                arcs[v][idx].capacity = arcs[v][idx].capacity - pushed;
+                arcs[to][rev].capacity = arcs[to][rev].capacity + pushed;
+                return pushed;
+            }
+        }
+        next[v] += 1;
+    }
+    C::zero()
+}
+
+/// The first time a set of things repeating with the given `periods` all
+/// line up again, their lowest common multiple.
+pub fn sync_period<N: Integer + Clone, I: IntoIterator<Item = N>>(periods: I) -> N {
+    periods.into_iter().fold(N::one(), |acc, p| acc.lcm(&p))
+}
+
+pub trait Connected {
+    type Item;
+    fn get_neighbors(&self, node: &Self::Item) -> Vec<Self::Item>;
+}
+
+/// Disjoint sets of the integers `0..n`, which can be merged together
+/// one pair at a time.
+#[derive(Clone, Debug)]
+pub struct UnionFind {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+    count: usize,
+}
+
+impl UnionFind {
+    /// Start with each of `0..n` in a set of its own.
+    pub fn new(n: usize) -> Self {
+        Self {
+            parent: (0..n).collect(),
+            size: vec![1; n],
+            count: n,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    /// The representative member of the set containing `x`.
+    pub fn find(&mut self, x: usize) -> usize {
+        let mut root = x;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        // Point everything on the way straight at the root
+        let mut x = x;
+        while self.parent[x] != root {
+            let next = self.parent[x];
+            self.parent[x] = root;
+            x = next;
+        }
+        root
+    }
+
+    /// Merge the sets containing `a` and `b`. Returns false if they were
+    /// already the same set.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        if self.size[a] < self.size[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+        self.count -= 1;
+        true
+    }
+
+    pub fn same_set(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// Size of the set containing `x`.
+    pub fn set_size(&mut self, x: usize) -> usize {
+        let root = self.find(x);
+        self.size[root]
+    }
+
+    /// Number of separate sets.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Sizes of all the sets, largest first.
+    pub fn set_sizes(&self) -> Vec<usize> {
+        let mut output: Vec<usize> = (0..self.len())
+            .filter(|&x| self.parent[x] == x)
+            .map(|x| self.size[x])
+            .collect();
+        output.sort_by(|a, b| b.cmp(a));
+        output
+    }
+}
+
+/// Connected components of a graph, see `connected_components`.
+#[derive(Clone, Debug)]
+pub struct Components<T: Clone + Eq + Hash> {
+    /// The nodes in each component. Components are in the order their first
+    /// node was given, as are the nodes within them.
+    pub components: Vec<Vec<T>>,
+    /// Which component each node is in.
+    pub membership: HashMap<T, usize>,
+}
+
+impl<T: Clone + Eq + Hash> Components<T> {
+    /// Group `nodes` by their set in `sets`, where `nodes[i]` is `i` in
+    /// `sets`.
+    fn from_sets(nodes: Vec<T>, sets: &mut UnionFind) -> Self {
+        let mut components: Vec<Vec<T>> = vec![];
+        let mut membership = HashMap::new();
+        let mut roots: HashMap<usize, usize> = HashMap::new();
+        for (idx, node) in nodes.into_iter().enumerate() {
+            let root = sets.find(idx);
+            let component = *roots.entry(root).or_insert_with(|| {
+                components.push(vec![]);
+                components.len() - 1
+            });
+            components[component].push(node.clone());
+            membership.insert(node, component);
+        }
+        Self {
+            components,
+            membership,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    /// Number of nodes in each component, in the same order as
+    /// `components`.
+    pub fn sizes(&self) -> Vec<usize> {
+        self.components.iter().map(|c| c.len()).collect()
+    }
+
+    pub fn same_component(&self, a: &T, b: &T) -> bool {
+        match (self.membership.get(a), self.membership.get(b)) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// Every node reachable from `start` by following neighbors, including
+/// `start` itself.
+pub fn connected_nodes<T, U>(start: &T, graph: &U) -> HashSet<T>
+where
+    T: Clone + Eq + Hash,
+    U: Connected<Item = T>,
+{
+    let mut output = HashSet::from([start.clone()]);
+    let mut stack = vec![start.clone()];
+    while let Some(node) = stack.pop() {
+        for n in graph.get_neighbors(&node) {
+            if output.insert(n.clone()) {
+                stack.push(n);
+            }
+        }
+    }
+    output
+}
+
+/// Split `nodes` into connected components. Any neighbors not in `nodes`
+/// are ignored, and if neighbors are one way only they're still treated
+/// as joined both ways.
+pub fn connected_components<T, U, I>(nodes: I, graph: &U) -> Components<T>
+where
+    T: Clone + Eq + Hash,
+    U: Connected<Item = T>,
+    I: IntoIterator<Item = T>,
+{
+    let nodes: Vec<T> = nodes.into_iter().collect();
+    let index: HashMap<T, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(idx, n)| (n.clone(), idx))
+        .collect();
+    let mut sets = UnionFind::new(nodes.len());
+    for (idx, node) in nodes.iter().enumerate() {
+        for n in graph.get_neighbors(node) {
+            if let Some(&nidx) = index.get(&n) {
+                sets.union(idx, nidx);
+            }
+        }
+    }
+    Components::from_sets(nodes, &mut sets)
+}
+
+/// A node reached by `Bfs` or `Dfs`, along with how many steps it took to
+/// get there and the node it was reached from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Visit<T> {
+    pub node: T,
+    pub depth: usize,
+    /// None for the start nodes.
+    pub parent: Option<T>,
+}
+
+/// Breadth first traversal of a graph, yielding each reachable node once in
+/// order of the fewest steps from the start. Nodes are only looked at as
+/// they're asked for, so it can be stopped early, e.g. with `take_while`.
+pub struct Bfs<'a, U: Connected> {
+    graph: &'a U,
+    queue: VecDeque<Visit<U::Item>>,
+    visited: HashSet<U::Item>,
+}
+
+impl<'a, T: Clone + Eq + Hash, U: Connected<Item = T>> Bfs<'a, U> {
+    pub fn new(start: &T, graph: &'a U) -> Bfs<'a, U> {
+        Bfs::from_starts([start.clone()], graph)
+    }
+
+    /// Traverse out from all of `starts` at once, each at depth 0.
+    pub fn from_starts<I: IntoIterator<Item = T>>(starts: I, graph: &'a U) -> Bfs<'a, U> {
+        let mut visited = HashSet::new();
+        let queue = starts
+            .into_iter()
+            .filter(|s| visited.insert(s.clone()))
+            .map(|node| Visit {
+                node,
+                depth: 0,
+                parent: None,
+            })
+            .collect();
+        Bfs {
+            graph,
+            queue,
+            visited,
+        }
+    }
+
+    /// Every node found so far, which includes those queued up to be
+    /// yielded next as well as those already yielded.
+    pub fn visited(&self) -> &HashSet<T> {
+        &self.visited
+    }
+}
+
+impl<T: Clone + Eq + Hash, U: Connected<Item = T>> Iterator for Bfs<'_, U> {
+    type Item = Visit<T>;
+
+    fn next(&mut self) -> Option<Visit<T>> {
+        let visit = self.queue.pop_front()?;
+        for n in self.graph.get_neighbors(&visit.node) {
+            if self.visited.insert(n.clone()) {
+                self.queue.push_back(Visit {
+                    node: n,
+                    depth: visit.depth + 1,
+                    parent: Some(visit.node.clone()),
+                });
+            }
+        }
+        Some(visit)
+    }
+}
+
+/// Depth first traversal of a graph, yielding each reachable node once,
+/// before any of the nodes found from it. Like `Bfs`, it only does as much
+/// work as is asked of it. Depths are along the path taken to each node, so
+/// aren't necessarily the fewest steps.
+pub struct Dfs<'a, U: Connected> {
+    graph: &'a U,
+    stack: Vec<Visit<U::Item>>,
+    visited: HashSet<U::Item>,
+}
+
+impl<'a, T: Clone + Eq + Hash, U: Connected<Item = T>> Dfs<'a, U> {
+    pub fn new(start: &T, graph: &'a U) -> Dfs<'a, U> {
+        Dfs::from_starts([start.clone()], graph)
+    }
+
+    /// Traverse from each of `starts` in turn, skipping those already
+    /// reached from an earlier one.
+    pub fn from_starts<I: IntoIterator<Item = T>>(starts: I, graph: &'a U) -> Dfs<'a, U> {
+        let mut stack: Vec<Visit<T>> = starts
+            .into_iter()
+            .map(|node| Visit {
+                node,
+                depth: 0,
+                parent: None,
+            })
+            .collect();
+        stack.reverse();
+        Dfs {
+            graph,
+            stack,
+            visited: HashSet::new(),
+        }
+    }
+
+    /// Every node yielded so far.
+    pub fn visited(&self) -> &HashSet<T> {
+        &self.visited
+    }
+}
+
+impl<T: Clone + Eq + Hash, U: Connected<Item = T>> Iterator for Dfs<'_, U> {
+    type Item = Visit<T>;
+
+    fn next(&mut self) -> Option<Visit<T>> {
+        loop {
+            let visit = self.stack.pop()?;
+            if !self.visited.insert(visit.node.clone()) {
+                continue;
+            }
+            // Pushed in reverse so neighbors are explored in the order
+            // `get_neighbors` gives them
+            let neighbors = self.graph.get_neighbors(&visit.node);
+            for n in neighbors.into_iter().rev() {
+                if !self.visited.contains(&n) {
+                    self.stack.push(Visit {
+                        node: n,
+                        depth: visit.depth + 1,
+                        parent: Some(visit.node.clone()),
+                    });
+                }
+            }
+            return Some(visit);
+        }
+    }
+}
+
+/// Fewest steps path from `start` to `end`, following neighbors in the
+/// direction `get_neighbors` gives them. The path begins with `start` and
+/// ends with `end`.
+pub fn shortest_path<T, U>(start: &T, end: &T, graph: &U) -> Option<Vec<T>>
+where
+    T: Clone + Eq + Hash,
+    U: Connected<Item = T>,
+{
+    breadth_first(
+        vec![start.clone()],
+        |n| graph.get_neighbors(n),
+        |n| n == end,
+        T::clone,
+    )
+}
+
+/// Breadth first search out from all of `starts` at once until a node
+/// satisfying `goal` is found, returning the path to it. Nodes with the
+/// same `key` are treated as the same node.
+fn breadth_first<T, K, S, G, F>(
+    starts: Vec<T>,
+    mut successors: S,
+    mut goal: G,
+    key: F,
+) -> Option<Vec<T>>
+where
+    T: Clone,
+    K: Eq + Hash,
+    S: FnMut(&T) -> Vec<T>,
+    G: FnMut(&T) -> bool,
+    F: Fn(&T) -> K,
+{
+    let mut nodes: Vec<T> = vec![];
+    let mut prev: Vec<Option<usize>> = vec![];
+    let mut seen: HashSet<K> = HashSet::new();
+    for s in starts {
+        if seen.insert(key(&s)) {
+            nodes.push(s);
+            prev.push(None);
+        }
+    }
+
+    let mut queue: VecDeque<usize> = (0..nodes.len()).collect();
+    while let Some(idx) = queue.pop_front() {
+        if goal(&nodes[idx]) {
+            return Some(trace_path(&nodes, &prev, idx));
+        }
+        for n in successors(&nodes[idx]) {
+            if seen.insert(key(&n)) {
+                nodes.push(n);
+                prev.push(Some(idx));
+                queue.push_back(nodes.len() - 1);
+            }
+        }
+    }
+    None
+}
+
+/// Walk `prev` back from `idx` to a start, returning the path start first.
+fn trace_path<T: Clone>(nodes: &[T], prev: &[Option<usize>], idx: usize) -> Vec<T> {
+    let mut path = vec![];
+    let mut next = Some(idx);
+    while let Some(i) = next {
+        path.push(nodes[i].clone());
+        next = prev[i];
+    }
+    path.reverse();
+    path
+}
+
+/// Like `Connected`, but each step to a neighbor has a cost.
+pub trait WeightedConnected {
+    type Item;
+    type Cost;
+    fn get_weighted_neighbors(&self, node: &Self::Item) -> Vec<(Self::Item, Self::Cost)>;
+}
+
+/// Lowest cost path from `start` to `end` using Dijkstra's algorithm.
+/// Returns the total cost and the path, which begins with `start` and ends
+/// with `end`. Costs must not be negative.
+pub fn dijkstra<T, U>(start: &T, end: &T, graph: &U) -> Option<(U::Cost, Vec<T>)>
+where
+    T: Clone + Eq + Hash,
+    U: WeightedConnected<Item = T>,
+    U::Cost: Copy + Ord + Zero,
+{
+    astar(start, end, graph, |_| U::Cost::zero())
+}
+
+/// Lowest cost path from `start` to `end` using A*, guided by `heuristic`,
+/// an estimate of the remaining cost from a node to `end`. The estimate must
+/// never drop by more than the cost of a step, or overestimate the remaining
+/// cost, else the path found may not be the cheapest. Returns the same as
+/// `dijkstra`.
+pub fn astar<T, U, H>(start: &T, end: &T, graph: &U, heuristic: H) -> Option<(U::Cost, Vec<T>)>
+where
+    T: Clone + Eq + Hash,
+    U: WeightedConnected<Item = T>,
+    U::Cost: Copy + Ord + Zero,
+    H: Fn(&T) -> U::Cost,
+{
+    best_first(
+        vec![start.clone()],
+        |n| graph.get_weighted_neighbors(n),
+        |n| n == end,
+        heuristic,
+        T::clone,
+    )
+}
+
+/// A* search out from all of `starts` at once until a node satisfying
+/// `goal` is settled, returning its cost and the path to it. Nodes with the
+/// same `key` are treated as the same node.
+fn best_first<T, C, K, S, G, H, F>(
+    starts: Vec<T>,
+    mut successors: S,
+    mut goal: G,
+    heuristic: H,
+    key: F,
+) -> Option<(C, Vec<T>)>
+where
+    T: Clone,
+    C: Copy + Ord + Zero,
+    K: Eq + Hash,
+    S: FnMut(&T) -> Vec<(T, C)>,
+    G: FnMut(&T) -> bool,
+    H: Fn(&T) -> C,
+    F: Fn(&T) -> K,
+{
+    // Nodes are referred to by their index into `nodes` so that the heap
+    // doesn't need `T: Ord`.
+    let mut nodes: Vec<T> = vec![];
+    let mut index: HashMap<K, usize> = HashMap::new();
+    let mut costs: Vec<C> = vec![];
+    let mut prev: Vec<Option<usize>> = vec![];
+    let mut done: Vec<bool> = vec![];
+    let mut heap = BinaryHeap::new();
+    for s in starts {
+        if let std::collections::hash_map::Entry::Vacant(e) = index.entry(key(&s)) {
+            e.insert(nodes.len());
+            heap.push(Reverse((heuristic(&s), nodes.len())));
+            nodes.push(s);
+            costs.push(C::zero());
+            prev.push(None);
+            done.push(false);
+        }
+    }
+
+    while let Some(Reverse((_, idx))) = heap.pop() {
+        if done[idx] {
+            continue;
+        }
+        done[idx] = true;
+
+        let cost = costs[idx];
+        if goal(&nodes[idx]) {
+            return Some((cost, trace_path(&nodes, &prev, idx)));
+        }
+
+        for (n, step) in successors(&nodes[idx]) {
+            let new_cost = cost + step;
+            let k = key(&n);
+            let nidx = match index.get(&k) {
+                Some(&nidx) => nidx,
+                None => {
+                    let estimate = new_cost + heuristic(&n);
+                    nodes.push(n);
+                    costs.push(new_cost);
+                    prev.push(Some(idx));
+                    done.push(false);
+                    index.insert(k, nodes.len() - 1);
+                    heap.push(Reverse((estimate, nodes.len() - 1)));
+                    continue;
+                }
+            };
+            if !done[nidx] && new_cost < costs[nidx] {
+                // Keep the latest state seen for this key, as it's the one
+                // the cost belongs to
+                nodes[nidx] = n;
+                costs[nidx] = new_cost;
+                prev[nidx] = Some(idx);
+                heap.push(Reverse((new_cost + heuristic(&nodes[nidx]), nidx)));
+            }
+        }
+    }
+    None
+}
+
+type Successors<'a, T, C> = Box<dyn FnMut(&T) -> Vec<(T, C)> + 'a>;
+type Estimate<'a, T, C> = Box<dyn Fn(&T) -> C + 'a>;
+
+/// Search over states that aren't nodes of any graph, such as a position
+/// along with the keys collected so far. Made with `Search::new`, then
+/// optionally given a `heuristic` or `key`, and run with `bfs` or `run`.
+pub struct Search<'a, T, C, K = T> {
+    starts: Vec<T>,
+    successors: Successors<'a, T, C>,
+    goal: Box<dyn FnMut(&T) -> bool + 'a>,
+    heuristic: Option<Estimate<'a, T, C>>,
+    key: Box<dyn Fn(&T) -> K + 'a>,
+}
+
+impl<'a, T: Clone + Eq + Hash + 'a, C> Search<'a, T, C> {
+    /// Search from every state in `starts` for one satisfying `goal`, where
+    /// `successors` gives the states reachable in one step from a state and
+    /// the cost of getting to each.
+    pub fn new<I, S, G>(starts: I, successors: S, goal: G) -> Search<'a, T, C>
+    where
+        I: IntoIterator<Item = T>,
+        S: FnMut(&T) -> Vec<(T, C)> + 'a,
+        G: FnMut(&T) -> bool + 'a,
+    {
+        Search {
+            starts: starts.into_iter().collect(),
+            successors: Box::new(successors),
+            goal: Box::new(goal),
+            heuristic: None,
+            key: Box::new(T::clone),
+        }
+    }
+}
+
+impl<'a, T: Clone, C: Copy + Ord + Zero, K: Eq + Hash> Search<'a, T, C, K> {
+    /// States with the same `key` are treated as the same state, so only
+    /// one of them is searched further: the first reached for `bfs`, and the
+    /// cheapest for `run`, which replaces the one kept if it turns up later.
+    /// Useful when states carry details that don't affect what comes next,
+    /// or have symmetries.
+    pub fn key<K2, F>(self, key: F) -> Search<'a, T, C, K2>
+    where
+        K2: Eq + Hash,
+        F: Fn(&T) -> K2 + 'a,
+    {
+        Search {
+            starts: self.starts,
+            successors: self.successors,
+            goal: self.goal,
+            heuristic: self.heuristic,
+            key: Box::new(key),
+        }
+    }
+
+    /// Guide `run` with an estimate of the remaining cost to a goal, turning
+    /// it into A*. The same rules apply as for `astar`.
+    pub fn heuristic<H: Fn(&T) -> C + 'a>(mut self, heuristic: H) -> Search<'a, T, C, K> {
+        self.heuristic = Some(Box::new(heuristic));
+        self
+    }
+
+    /// Fewest steps path from a start to a goal, ignoring step costs.
+    pub fn bfs(mut self) -> Option<Vec<T>> {
+        let successors = &mut self.successors;
+        breadth_first(
+            self.starts,
+            |s| successors(s).into_iter().map(|(n, _)| n).collect(),
+            self.goal,
+            self.key,
+        )
+    }
+
+    /// Lowest cost path from a start to a goal, using Dijkstra's algorithm
+    /// or A* if given a heuristic. Returns the total cost and the path.
+    pub fn run(self) -> Option<(C, Vec<T>)> {
+        let heuristic = self.heuristic;
+        best_first(
+            self.starts,
+            self.successors,
+            self.goal,
+            |s| heuristic.as_ref().map_or(C::zero(), |h| h(s)),
+            self.key,
+        )
+    }
+}
+
+/// Every shortest path out of one start node, as found by
+/// `all_shortest_paths`.
+#[derive(Clone, Debug)]
+pub struct ShortestPaths<T: Clone + Eq + Hash, C> {
+    pub start: T,
+    /// Cost of the cheapest path to each reachable node.
+    pub distances: HashMap<T, C>,
+    /// For each reachable node, every node just before it on some cheapest
+    /// path. The start node has none.
+    pub predecessors: HashMap<T, Vec<T>>,
+    /// Reachable nodes in the order their distances were settled, so every
+    /// node comes after all its predecessors.
+    pub order: Vec<T>,
+}
+
+impl<T: Clone + Eq + Hash, C> ShortestPaths<T, C> {
+    /// Number of distinct cheapest paths from the start to every reachable
+    /// node.
+    pub fn path_counts(&self) -> HashMap<T, BigUint> {
+        let mut output: HashMap<T, BigUint> = HashMap::new();
+        for node in &self.order {
+            let count = if *node == self.start {
+                BigUint::one()
+            } else {
+                self.predecessors[node].iter().map(|p| &output[p]).sum()
+            };
+            output.insert(node.clone(), count);
+        }
+        output
+    }
+
+    /// Number of distinct cheapest paths from the start to `end`, zero if
+    /// `end` can't be reached.
+    pub fn path_count(&self, end: &T) -> BigUint {
+        self.path_counts().remove(end).unwrap_or_default()
+    }
+
+    /// Every node lying on at least one cheapest path from the start to
+    /// `end`, including both ends. Empty if `end` can't be reached.
+    pub fn nodes_on_paths(&self, end: &T) -> HashSet<T> {
+        let mut output = HashSet::new();
+        if !self.distances.contains_key(end) {
+            return output;
+        }
+        let mut stack = vec![end.clone()];
+        output.insert(end.clone());
+        while let Some(node) = stack.pop() {
+            for p in &self.predecessors[&node] {
+                if output.insert(p.clone()) {
+                    stack.push(p.clone());
+                }
+            }
+        }
+        output
+    }
+}
+
+/// Run Dijkstra's algorithm out from `start` over the whole graph, keeping
+/// every predecessor that ties for the cheapest path rather than just one.
+/// Steps should have positive cost, as zero cost cycles make the number of
+/// cheapest paths unbounded.
+pub fn all_shortest_paths<T, U>(start: &T, graph: &U) -> ShortestPaths<T, U::Cost>
+where
+    T: Clone + Eq + Hash,
+    U: WeightedConnected<Item = T>,
+    U::Cost: Copy + Ord + Zero,
+{
+    let mut nodes: Vec<T> = vec![start.clone()];
+    let mut index: HashMap<T, usize> = HashMap::new();
+    let mut costs: Vec<U::Cost> = vec![U::Cost::zero()];
+    let mut prev: Vec<Vec<usize>> = vec![vec![]];
+    let mut done: Vec<bool> = vec![false];
+    let mut order: Vec<usize> = vec![];
+    index.insert(start.clone(), 0);
+
+    let mut heap = BinaryHeap::new();
+    heap.push(Reverse((U::Cost::zero(), 0)));
+    while let Some(Reverse((cost, idx))) = heap.pop() {
+        if done[idx] {
+            continue;
+        }
+        done[idx] = true;
+        order.push(idx);
+
+        for (n, step) in graph.get_weighted_neighbors(&nodes[idx]) {
+            let new_cost = cost + step;
+            let nidx = match index.get(&n) {
+                Some(&nidx) => nidx,
+                None => {
+                    nodes.push(n.clone());
+                    costs.push(new_cost);
+                    prev.push(vec![idx]);
+                    done.push(false);
+                    index.insert(n, nodes.len() - 1);
+                    heap.push(Reverse((new_cost, nodes.len() - 1)));
+                    continue;
+                }
+            };
+            if done[nidx] {
+                continue;
+            }
+            if new_cost < costs[nidx] {
+                costs[nidx] = new_cost;
+                prev[nidx] = vec![idx];
+                heap.push(Reverse((new_cost, nidx)));
+            } else if new_cost == costs[nidx] && !prev[nidx].contains(&idx) {
+                prev[nidx].push(idx);
+            }
+        }
+    }
+
+    let mut distances = HashMap::new();
+    let mut predecessors = HashMap::new();
+    for &idx in &order {
+        distances.insert(nodes[idx].clone(), costs[idx]);
+        predecessors.insert(
+            nodes[idx].clone(),
+            prev[idx].iter().map(|&p| nodes[p].clone()).collect(),
+        );
+    }
+    ShortestPaths {
+        start: start.clone(),
+        distances,
+        predecessors,
+        order: order.into_iter().map(|idx| nodes[idx].clone()).collect(),
+    }
+}
+
+/// Treats a `Connected` graph as a `WeightedConnected` one where every step
+/// costs 1, so it can be searched with `dijkstra` or `astar`.
+pub struct Unweighted<'a, U>(pub &'a U);
+
+impl<U: Connected> WeightedConnected for Unweighted<'_, U> {
+    type Item = U::Item;
+    type Cost = usize;
+    fn get_weighted_neighbors(&self, node: &Self::Item) -> Vec<(Self::Item, usize)> {
+        self.0
+            .get_neighbors(node)
+            .into_iter()
+            .map(|n| (n, 1))
+            .collect()
+    }
+}
+
+/// Adjacency map from each node to its neighbors and the cost of stepping
+/// to them, as made by `contract_corridors`.
+impl<T: Clone + Eq + Hash, C: Copy> WeightedConnected for HashMap<T, Vec<(T, C)>> {
+    type Item = T;
+    type Cost = C;
+    fn get_weighted_neighbors(&self, node: &T) -> Vec<(T, C)> {
+        self.get(node).cloned().unwrap_or_default()
+    }
+}
+
+/// Collapse the corridors of `graph` into single weighted edges, leaving
+/// only junctions: the nodes in `keep`, nodes with 3 or more neighbors, and
+/// any node where a corridor forks. Each edge's weight is the number of
+/// steps along the corridor. Only the part of the graph reachable from
+/// `keep` is explored, and corridors ending in a dead end are dropped.
+pub fn contract_corridors<T, U, I>(graph: &U, keep: I) -> HashMap<T, Vec<(T, usize)>>
+where
+    T: Clone + Eq + Hash,
+    U: Connected<Item = T>,
+    I: IntoIterator<Item = T>,
+{
+    let keep: HashSet<T> = keep.into_iter().collect();
+    let mut junctions: HashSet<T> = keep.clone();
+    let mut todo: Vec<T> = keep.into_iter().collect();
+    let mut output: HashMap<T, Vec<(T, usize)>> = HashMap::new();
+
+    while let Some(junction) = todo.pop() {
+        let mut edges = vec![];
+        for first in graph.get_neighbors(&junction) {
+            let mut prev = junction.clone();
+            let mut current = first;
+            let mut steps = 1;
+            let mut seen = HashSet::new();
+            let end = loop {
+                if junctions.contains(&current) {
+                    break Some(current);
+                }
+                let neighbors = graph.get_neighbors(&current);
+                let next: Vec<T> = neighbors.iter().filter(|&n| *n != prev).cloned().collect();
+                if neighbors.len() > 2 || next.len() > 1 {
+                    junctions.insert(current.clone());
+                    todo.push(current.clone());
+                    break Some(current);
+                }
+                // Dead ends, and loops with nowhere to go
+                if next.is_empty() || !seen.insert(current.clone()) {
+                    break None;
+                }
+                prev = std::mem::replace(&mut current, next[0].clone());
+                steps += 1;
+            };
+            if let Some(end) = end {
+                edges.push((end, steps));
+            }
+        }
+        output.insert(junction, edges);
+    }
+    output
+}
+
+/// Longest path from `start` to `end` that doesn't visit any node twice,
+/// found by trying them all with a depth first search. This takes
+/// exponential time, so it's only for small graphs such as those from
+/// `contract_corridors`, and panics if more than 128 nodes are reachable.
+/// Returns the total cost and the path.
+pub fn longest_path<T, U>(start: &T, end: &T, graph: &U) -> Option<(U::Cost, Vec<T>)>
+where
+    T: Clone + Eq + Hash,
+    U: WeightedConnected<Item = T>,
+    U::Cost: Copy + Ord + Zero,
+{
+    // Number the reachable nodes so the visited set fits in a bitmask
+    let mut nodes: Vec<T> = vec![start.clone()];
+    let mut index: HashMap<T, usize> = HashMap::from([(start.clone(), 0)]);
+    let mut adjacent: Vec<Vec<(usize, U::Cost)>> = vec![];
+    while adjacent.len() < nodes.len() {
+        let mut edges = vec![];
+        for (n, cost) in graph.get_weighted_neighbors(&nodes[adjacent.len()]) {
+            let idx = *index.entry(n.clone()).or_insert_with(|| {
+                nodes.push(n);
+                nodes.len() - 1
+            });
+            edges.push((idx, cost));
+        }
+        adjacent.push(edges);
+    }
+    assert!(
+        nodes.len() <= 128,
+        "Too many nodes for longest_path, try contract_corridors first"
+    );
+    let end = *index.get(end)?;
+
+    let mut best: Option<(U::Cost, Vec<usize>)> = None;
+    let mut path = vec![0];
+    longest_path_search(&adjacent, end, 1, U::Cost::zero(), &mut path, &mut best);
+    best.map(|(cost, path)| (cost, path.into_iter().map(|i| nodes[i].clone()).collect()))
+}
+
+fn longest_path_search<C: Copy + Ord + Zero>(
+    adjacent: &[Vec<(usize, C)>],
+    end: usize,
+    visited: u128,
+    cost: C,
+    path: &mut Vec<usize>,
+    best: &mut Option<(C, Vec<usize>)>,
+) {
+    let node = *path.last().unwrap();
+    if node == end {
+        if best.as_ref().is_none_or(|(b, _)| cost > *b) {
+            *best = Some((cost, path.clone()));
+        }
+        return;
+    }
+    for &(n, step) in &adjacent[node] {
+        if visited & (1 << n) == 0 {
+            path.push(n);
+            longest_path_search(adjacent, end, visited | (1 << n), cost + step, path, best);
+            path.pop();
+        }
+    }
+}
+
+/// Cheapest costs between every pair of a set of nodes, indexed by each
+/// node's position in `nodes`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DistanceMatrix<T: Clone + Eq + Hash, C> {
+    pub nodes: Vec<T>,
+    /// `distances[i][j]` is the cost from `nodes[i]` to `nodes[j]`, or None
+    /// if there's no path.
+    pub distances: Vec<Vec<Option<C>>>,
+    index: HashMap<T, usize>,
+}
+
+impl<T: Clone + Eq + Hash, C: Copy> DistanceMatrix<T, C> {
+    fn new(nodes: Vec<T>, distances: Vec<Vec<Option<C>>>) -> DistanceMatrix<T, C> {
+        let index = nodes.iter().cloned().zip(0..).collect();
+        DistanceMatrix {
+            nodes,
+            distances,
+            index,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn index_of(&self, node: &T) -> Option<usize> {
+        self.index.get(node).copied()
+    }
+
+    /// Cost from `from` to `to`, None if there's no path or either isn't in
+    /// the matrix.
+    pub fn distance(&self, from: &T, to: &T) -> Option<C> {
+        self.distances[self.index_of(from)?][self.index_of(to)?]
+    }
+
+    /// A smaller matrix over just `nodes`, in the order given. Panics if
+    /// any of them aren't in this matrix.
+    pub fn subset<I: IntoIterator<Item = T>>(&self, nodes: I) -> DistanceMatrix<T, C> {
+        let nodes: Vec<T> = nodes.into_iter().collect();
+        let idx: Vec<usize> = nodes
+            .iter()
+            .map(|n| self.index_of(n).expect("Node not in distance matrix"))
+            .collect();
+        let distances = idx
+            .iter()
+            .map(|&i| idx.iter().map(|&j| self.distances[i][j]).collect())
+            .collect();
+        DistanceMatrix::new(nodes, distances)
+    }
+}
+
+impl<T: Clone + Eq + Hash, C: Copy + Ord + Zero> DistanceMatrix<T, C> {
+    /// Cheapest path visiting every node in the matrix exactly once, using
+    /// the Held-Karp algorithm. The path begins at `start`, or wherever is
+    /// best if None, and if `round_trip` is set it finishes back at the
+    /// beginning, which is repeated at the end of the returned path. Takes
+    /// time and memory exponential in the number of nodes, so panics if the
+    /// matrix has more than 20.
+    pub fn shortest_tour(&self, start: Option<&T>, round_trip: bool) -> Option<(C, Vec<T>)> {
+        self.held_karp(start, round_trip, |a, b| a < b)
+    }
+
+    /// Like `shortest_tour`, but for the most expensive path. Also panics if
+    /// the matrix has more than 20 nodes.
+    pub fn longest_tour(&self, start: Option<&T>, round_trip: bool) -> Option<(C, Vec<T>)> {
+        self.held_karp(start, round_trip, |a, b| a > b)
+    }
+
+    fn held_karp<F>(&self, start: Option<&T>, round_trip: bool, better: F) -> Option<(C, Vec<T>)>
+    where
+        F: Fn(C, C) -> bool,
+    {
+        let n = self.len();
+        assert!(n <= 20, "Too many nodes for held_karp");
+        if n == 0 {
+            return None;
+        }
+        let starts: Vec<usize> = match start {
+            Some(s) => vec![self.index_of(s)?],
+            None if round_trip => vec![0],
+            None => (0..n).collect(),
+        };
+
+        // best[mask * n + last] is the cost of the best path through the
+        // nodes in mask that ends at last, and the node before last on it
+        let mut best: Vec<Option<(C, usize)>> = vec![None; n << n];
+        for &s in &starts {
+            best[(1 << s) * n + s] = Some((C::zero(), s));
+        }
+        for mask in 1..1usize << n {
+            for last in 0..n {
+                let Some((cost, _)) = best[mask * n + last] else {
+                    continue;
+                };
+                for next in (0..n).filter(|next| mask & (1 << next) == 0) {
+                    let Some(step) = self.distances[last][next] else {
+                        continue;
+                    };
+                    let entry = &mut best[(mask | (1 << next)) * n + next];
+                    if entry.is_none_or(|(c, _)| better(cost + step, c)) {
+                        *entry = Some((cost + step, last));
+                    }
+                }
+            }
+        }
+
+        let full = (1 << n) - 1;
+        let mut end: Option<(C, usize)> = None;
+        for (last, entry) in best[full * n..].iter().enumerate() {
+            let Some((mut cost, _)) = *entry else {
+                continue;
+            };
+            if round_trip {
+                match self.distances[last][starts[0]] {
+                    Some(step) => cost = cost + step,
+                    None => continue,
+                }
+            }
+            if end.is_none_or(|(c, _)| better(cost, c)) {
+                end = Some((cost, last));
+            }
+        }
+
+        let (cost, mut last) = end?;
+        let mut path = vec![];
+        if round_trip {
+            path.push(self.nodes[starts[0]].clone());
+        }
+        let mut mask = full;
+        while mask != 0 {
+            path.push(self.nodes[last].clone());
+            let prev = best[mask * n + last].unwrap().1;
+            mask &= !(1 << last);
+            last = prev;
+        }
+        path.reverse();
+        Some((cost, path))
+    }
+
+    /// Most total reward that can be collected by travelling from `start`
+    /// to other nodes in the matrix within `budget`. Each node is worth
+    /// `reward(node, remaining)` when reached with `remaining` budget left,
+    /// after also spending `stay` there, and is only rewarded once. Nodes
+    /// that aren't worth anything should be left out of the matrix, as the
+    /// search tries every order of visiting them. Panics if the matrix has
+    /// more than 64 nodes.
+    pub fn max_reward<R, F>(&self, start: &T, budget: C, stay: C, reward: F) -> R
+    where
+        C: Sub<Output = C>,
+        R: Copy + Ord + Zero,
+        F: Fn(&T, C) -> R,
+    {
+        self.rewards_by_set(start, budget, stay, reward)
+            .into_values()
+            .max()
+            .unwrap_or_else(R::zero)
+    }
+
+    /// Like `max_reward`, but with two agents setting off from `start` at
+    /// the same time, each visiting different nodes.
+    pub fn max_reward_pair<R, F>(&self, start: &T, budget: C, stay: C, reward: F) -> R
+    where
+        C: Sub<Output = C>,
+        R: Copy + Ord + Zero,
+        F: Fn(&T, C) -> R,
+    {
+        let mut sets: Vec<(u64, R)> = self
+            .rewards_by_set(start, budget, stay, reward)
+            .into_iter()
+            .collect();
+        sets.sort_by_key(|s| Reverse(s.1));
+        let mut output = R::zero();
+        for (i, &(mask_a, reward_a)) in sets.iter().enumerate() {
+            if reward_a + reward_a <= output {
+                break;
+            }
+            for &(mask_b, reward_b) in &sets[i..] {
+                if reward_a + reward_b <= output {
+                    break;
+                }
+                if mask_a & mask_b == 0 {
+                    output = reward_a + reward_b;
+                }
+            }
+        }
+        output
+    }
+
+    /// Best reward for each set of nodes that can be visited within the
+    /// budget, as a bitmask of node indices.
+    fn rewards_by_set<R, F>(&self, start: &T, budget: C, stay: C, reward: F) -> HashMap<u64, R>
+    where
+        C: Sub<Output = C>,
+        R: Copy + Ord + Zero,
+        F: Fn(&T, C) -> R,
+    {
+        assert!(self.len() <= 64, "Too many nodes for max_reward");
+        let start = self.index_of(start).expect("Start not in distance matrix");
+        let mut output = HashMap::new();
+        let mut stack = vec![(start, budget, 1u64 << start, R::zero())];
+        while let Some((node, remaining, mask, total)) = stack.pop() {
+            let key = mask & !(1 << start);
+            let entry = output.entry(key).or_insert(total);
+            *entry = (*entry).max(total);
+            for next in (0..self.len()).filter(|next| mask & (1 << next) == 0) {
+                let Some(step) = self.distances[node][next] else {
+                    continue;
+                };
+                let cost = step + stay;
+                if cost <= remaining {
+                    let left = remaining - cost;
+                    let gained = reward(&self.nodes[next], left);
+                    stack.push((next, left, mask | (1 << next), total + gained));
+                }
+            }
+        }
+        output
+    }
+}
+
+/// Cheapest costs between every pair of nodes reachable from `nodes`, using
+/// the Floyd-Warshall algorithm. Takes cubic time in the number of nodes,
+/// so use `subset` to cut the result down to the nodes of interest.
+pub fn floyd_warshall<T, U, I>(nodes: I, graph: &U) -> DistanceMatrix<T, U::Cost>
+where
+    T: Clone + Eq + Hash,
+    U: WeightedConnected<Item = T>,
+    U::Cost: Copy + Ord + Zero,
+    I: IntoIterator<Item = T>,
+{
+    let mut nodes: Vec<T> = nodes.into_iter().collect();
+    let mut index: HashMap<T, usize> = HashMap::new();
+    nodes.retain(|n| {
+        if index.contains_key(n) {
+            false
+        } else {
+            index.insert(n.clone(), index.len());
+            true
+        }
+    });
+    let mut edges = vec![];
+    let mut i = 0;
+    while i < nodes.len() {
+        for (n, cost) in graph.get_weighted_neighbors(&nodes[i]) {
+            let j = *index.entry(n.clone()).or_insert_with(|| {
+                nodes.push(n);
+                nodes.len() - 1
+            });
+            edges.push((i, j, cost));
+        }
+        i += 1;
+    }
+
+    let n = nodes.len();
+    let mut dist: Vec<Vec<Option<U::Cost>>> = vec![vec![None; n]; n];
+    for (i, row) in dist.iter_mut().enumerate() {
+        row[i] = Some(U::Cost::zero());
+    }
+    for (i, j, cost) in edges {
+        if dist[i][j].is_none_or(|d| cost < d) {
+            dist[i][j] = Some(cost);
+        }
+    }
+    for k in 0..n {
+        let via = dist[k].clone();
+        for row in dist.iter_mut() {
+            let Some(ik) = row[k] else { continue };
+            for (d, kj) in row.iter_mut().zip(&via) {
+                if let Some(kj) = kj {
+                    if d.is_none_or(|d| ik + *kj < d) {
+                        *d = Some(ik + *kj);
+                    }
+                }
+            }
+        }
+    }
+    DistanceMatrix::new(nodes, dist)
+}
+
+/// Number of steps between every pair of `nodes`, found with a breadth
+/// first search out from each one. Much quicker than `floyd_warshall` when
+/// only a few nodes of a large graph are of interest.
+pub fn all_pairs_bfs<T, U, I>(nodes: I, graph: &U) -> DistanceMatrix<T, usize>
+where
+    T: Clone + Eq + Hash,
+    U: Connected<Item = T>,
+    I: IntoIterator<Item = T>,
+{
+    let mut nodes: Vec<T> = nodes.into_iter().collect();
+    let mut index: HashMap<T, usize> = HashMap::new();
+    nodes.retain(|n| {
+        if index.contains_key(n) {
+            false
+        } else {
+            index.insert(n.clone(), index.len());
+            true
+        }
+    });
+
+    let mut distances = vec![];
+    for start in &nodes {
+        let mut row = vec![None; nodes.len()];
+        let mut found = 0;
+        let mut seen = HashSet::from([start.clone()]);
+        let mut queue = VecDeque::from([(start.clone(), 0)]);
+        while let Some((node, steps)) = queue.pop_front() {
+            if let Some(&i) = index.get(&node) {
+                row[i] = Some(steps);
+                found += 1;
+                if found == nodes.len() {
+                    break;
+                }
+            }
+            for n in graph.get_neighbors(&node) {
+                if seen.insert(n.clone()) {
+                    queue.push_back((n, steps + 1));
+                }
+            }
+        }
+        distances.push(row);
+    }
+    DistanceMatrix::new(nodes, distances)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid2d::{chebyshev, get_orthogonal_neighbors, manhattan, Direction, SizedBoard};
+
+    impl Connected for HashSet<(usize, usize)> {
+        type Item = (usize, usize);
+        fn get_neighbors(&self, node: &Self::Item) -> Vec<Self::Item> {
+            let mut candidates = vec![];
+            candidates.push((node.0, node.1 + 1));
+            candidates.push((node.0 + 1, node.1));
+            if node.1 > 0 {
+                candidates.push((node.0, node.1 - 1));
+            }
+            if node.0 > 0 {
+                candidates.push((node.0 - 1, node.1));
+            }
+
+            let mut output = vec![];
+            for candidate in candidates {
+                if self.contains(&candidate) {
+                    output.push(candidate);
+                }
+            }
+            output
+        }
+    }
+
+    #[test]
+    fn nodes() {
+        let n1 = Node::new(12);
+        assert_eq!(n1.value, 12);
+    }
+
+    #[test]
+    fn graphs() {
+        let mut g = Graph::<i64>::new();
+        let id0 = g.add_node(Node::new(12));
+        assert_eq!(g.nodes[0].value, 12);
+
+        let id1 = g.add_node_with_value(15);
+        assert_eq!(g.nodes[1].value, 15);
+
+        let id2 = g.add_node_with_value(1);
+        g.add_node_with_value(2);
+        g.add_node_with_value(3);
+
+        // Grab references to a couple Nodes
+        let node_ids = g.get_node_ids();
+        assert_eq!(node_ids, vec![id0, id1, id2, g.nodes[3].id, g.nodes[4].id]);
+        assert_eq!(node_ids, (0..5).map(NodeId).collect::<Vec<_>>());
+        assert_eq!(g.get_id_from_value(&1), Some(NodeId(2)));
+        assert_eq!(NodeId(2).to_string(), "n2");
+
+        g.add_edge(id0, id1);
+        g.add_edge(id0, id2);
+        let neighbors = g.get_node_neighbors(id0);
+        let mut testset = HashSet::new();
+        testset.insert(id1);
+        testset.insert(id2);
+        assert_eq!(neighbors, testset);
+
+        // Edges can be walked from either end, and adding the reverse edge
+        // doesn't duplicate it
+        g.add_edge(id1, id0);
+        g.add_edge(id1, id0);
+        assert_eq!(g.get_node_neighbors(id1), HashSet::from([id0]));
+        assert_eq!(g.neighbors(id0).count(), 2);
+        assert_eq!(g.get_node_edges(id1), HashSet::from([(id0, id1)]));
+        assert_eq!(g.edges.len(), 2);
+        assert_eq!(g.get_node_from_id(id2).unwrap().value, 1);
+        g.get_node_from_id_mut(id2).unwrap().value = 7;
+        assert_eq!(g.get_node_values(&[id2, id1]), vec![Some(7), Some(15)]);
+        assert_eq!(shortest_path(&id2, &id1, &g).map(|p| p.len()), Some(3));
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn uuids() {
+        let mut g = Graph::<i64>::new();
+        let node = Node::new(12);
+        let uuid = node.uuid;
+        let id = g.add_node(node);
+        g.add_node_with_value(13);
+        assert_eq!(g.get_id_from_uuid(uuid), Some(id));
+        assert_ne!(g.nodes[0].uuid, g.nodes[1].uuid);
+    }
+
+    #[test]
+    fn directed() {
+        let mut g = Graph::new_directed();
+        let a = g.add_node_with_value('a');
+        let b = g.add_node_with_value('b');
+        let c = g.add_node_with_value('c');
+        g.add_edge(a, b);
+        g.add_edge(a, c);
+        g.add_edge(c, b);
+        g.add_edge(b, a);
+        g.add_edge(c, c);
+        assert!(g.is_directed());
+        assert_eq!(g.edges.len(), 5);
+        assert_eq!(
+            g.out_neighbors(a).collect::<HashSet<_>>(),
+            HashSet::from([b, c])
+        );
+        assert_eq!(
+            g.in_neighbors(b).collect::<HashSet<_>>(),
+            HashSet::from([a, c])
+        );
+        assert_eq!(g.get_node_neighbors(b), HashSet::from([a, c]));
+        assert_eq!((g.out_degree(c), g.in_degree(c)), (2, 2));
+        assert_eq!(g.neighbors(c).count(), 3);
+        assert!(g.has_edge(c, b));
+        assert!(!g.has_edge(b, c));
+
+        assert_eq!(g.remove_edge(b, c), None);
+        assert_eq!(g.remove_edge(c, b), Some(()));
+        assert_eq!(g.in_neighbors(b).collect::<Vec<_>>(), vec![a]);
+        assert_eq!(g.get_node_edges(c), HashSet::from([(a, c), (c, c)]));
+        assert_eq!(shortest_path(&c, &b, &g), None);
+        assert_eq!(shortest_path(&b, &c, &g), Some(vec![b, a, c]));
+
+        let mut g = Graph::new();
+        let a = g.add_node_with_value('a');
+        let b = g.add_node_with_value('b');
+        g.add_edge(a, b);
+        assert_eq!((g.out_degree(b), g.in_degree(b)), (1, 1));
+        assert_eq!(g.remove_edge(b, a), Some(()));
+        assert!(g.edges.is_empty());
+        assert_eq!(g.neighbors(a).count(), 0);
+    }
+
+    #[test]
+    fn edge_data() {
+        let mut g: Graph<&str, u32> = Graph::with_edge_data();
+        let ids: Vec<NodeId> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|v| g.add_node_with_value(*v))
+            .collect();
+        g.add_edge_with_data(ids[0], ids[1], 7);
+        g.add_edge_with_data(ids[1], ids[3], 2);
+        g.add_edge_with_data(ids[0], ids[2], 1);
+        g.add_edge_with_data(ids[2], ids[3], 9);
+        assert_eq!(g.get_edge_data(ids[3], ids[2]), Some(&9));
+        assert_eq!(g.get_edge_data(ids[0], ids[3]), None);
+
+        let (cost, path) = dijkstra(&ids[0], &ids[3], &g).unwrap();
+        assert_eq!(cost, 9);
+        assert_eq!(
+            g.get_node_values(&path),
+            vec![Some("a"), Some("b"), Some("d")]
+        );
+
+        *g.get_edge_data_mut(ids[1], ids[0]).unwrap() = 20;
+        g.add_edge_with_data(ids[3], ids[2], 3);
+        assert_eq!(g.edges.len(), 4);
+        assert_eq!(dijkstra(&ids[0], &ids[3], &g).unwrap().0, 4);
+        assert_eq!(g.remove_edge(ids[1], ids[0]), Some(20));
+
+        // Labels rather than weights
+        let mut g: Graph<u8, char> = Graph::new_directed_with_edge_data();
+        let a = g.add_node_with_value(1);
+        let b = g.add_node_with_value(2);
+        g.add_edge_with_data(a, b, 'x');
+        assert_eq!(g.get_edge_data(b, a), None);
+        assert_eq!(g.get_weighted_neighbors(&a), vec![(b, 'x')]);
+    }
+
+    #[test]
+    fn components() {
+        let mut g = Graph::<u32>::new_directed();
+        let ids: Vec<NodeId> = (0..7).map(|v| g.add_node_with_value(v)).collect();
+        g.add_edge(ids[0], ids[1]);
+        g.add_edge(ids[2], ids[1]);
+        g.add_edge(ids[3], ids[4]);
+        g.add_edge(ids[5], ids[5]);
+        assert_eq!(g.get_connected_nodes(ids[1]), vec![ids[1], ids[0], ids[2]]);
+        assert_eq!(g.get_connected_nodes(ids[6]), vec![ids[6]]);
+        assert_eq!(connected_nodes(&ids[1], &g), HashSet::from([ids[1]]));
+        assert_eq!(
+            connected_nodes(&ids[0], &g),
+            HashSet::from([ids[0], ids[1]])
+        );
+
+        let components = g.connected_components();
+        assert_eq!(components.len(), 4);
+        assert_eq!(components.sizes(), vec![3, 2, 1, 1]);
+        assert_eq!(components.components[1], vec![ids[3], ids[4]]);
+        assert!(components.same_component(&ids[2], &ids[0]));
+        assert!(!components.same_component(&ids[2], &ids[3]));
+
+        let generic = connected_components(g.get_node_ids(), &g);
+        assert_eq!(generic.components, components.components);
+
+        // Two separate blocks of grid points
+        let open: HashSet<(usize, usize)> = (0..10)
+            .flat_map(|i| (0..10).map(move |j| (i, j)))
+            .filter(|&(i, _)| i != 4)
+            .collect();
+        let mut nodes: Vec<(usize, usize)> = open.iter().copied().collect();
+        nodes.sort();
+        let components = connected_components(nodes, &open);
+        assert_eq!(components.sizes(), vec![40, 50]);
+        assert_eq!(components.membership[&(9, 9)], 1);
+    }
+
+    #[test]
+    fn union_find() {
+        // Join the closest pairs of points into circuits
+        let points: Vec<(i64, i64, i64)> = vec![
+            (0, 0, 0),
+            (1, 0, 0),
+            (10, 10, 10),
+            (10, 11, 10),
+            (0, 2, 0),
+            (50, 50, 50),
+        ];
+        let mut pairs = vec![];
+        for i in 0..points.len() {
+            for j in i + 1..points.len() {
+                let (a, b) = (points[i], points[j]);
+                let d = (a.0 - b.0).pow(2) + (a.1 - b.1).pow(2) + (a.2 - b.2).pow(2);
+                pairs.push((d, i, j));
+            }
+        }
+        pairs.sort();
+
+        let mut circuits = UnionFind::new(points.len());
+        assert_eq!(circuits.count(), 6);
+        for &(_, i, j) in pairs.iter().take(3) {
+            circuits.union(i, j);
+        }
+        assert_eq!(circuits.set_sizes(), vec![3, 2, 1]);
+        assert!(circuits.same_set(4, 1));
+        assert_eq!(circuits.set_size(3), 2);
+        assert!(!circuits.union(0, 4));
+
+        // Keep going until everything is on one circuit
+        let mut last = None;
+        for &(_, i, j) in &pairs {
+            if circuits.union(i, j) && circuits.count() == 1 {
+                last = Some((i, j));
+                break;
+            }
+        }
+        assert_eq!(last.map(|(i, j)| points[i].0 * points[j].0), Some(500));
+    }
+
+    fn steps(input: &str) -> Graph<char> {
+        let mut g = Graph::new_directed();
+        for line in input.lines() {
+            let words: Vec<&str> = line.split(' ').collect();
+            let ids: Vec<NodeId> = [words[1], words[7]]
+                .iter()
+                .map(|w| {
+                    let c = w.chars().next().unwrap();
+                    g.get_id_from_value(&c)
+                        .unwrap_or_else(|| g.add_node_with_value(c))
+                })
+                .collect();
+            g.add_edge(ids[0], ids[1]);
+        }
+        g
+    }
+
+    #[test]
+    fn topological() {
+        let g = steps(
+            "Step C must be finished before step A can begin.
+Step C must be finished before step F can begin.
+Step A must be finished before step B can begin.
+Step A must be finished before step D can begin.
+Step B must be finished before step E can begin.
+Step D must be finished before step E can begin.
+Step F must be finished before step E can begin.",
+        );
+        let values = |ids: &[NodeId]| -> String {
+            g.get_node_values(ids)
+                .into_iter()
+                .map(|v| v.unwrap())
+                .collect()
+        };
+        assert_eq!(values(&g.topological_sort().unwrap()), "CABDFE");
+        assert_eq!(
+            values(&g.topological_sort_by(|a, b| b.cmp(a)).unwrap()),
+            "CFADBE"
+        );
+        assert_eq!(g.find_cycle(), None);
+
+        let schedule = g.schedule(2, |c| *c as u64 - 'A' as u64 + 1).unwrap();
+        assert_eq!(schedule.total_time, 15);
+        let order: Vec<NodeId> = schedule.steps.iter().map(|s| s.node).collect();
+        assert_eq!(values(&order), "CAFBDE");
+        assert_eq!(schedule.steps[2].worker, 1);
+        assert_eq!((schedule.steps[2].start, schedule.steps[2].end), (3, 9));
+
+        let mut g = g;
+        let e = g.get_id_from_value(&'E').unwrap();
+        let a = g.get_id_from_value(&'A').unwrap();
+        g.add_edge(e, a);
+        let cycle = g.topological_sort().unwrap_err();
+        assert!(cycle.len() == 3 && cycle.contains(&e) && cycle.contains(&a));
+        for i in 0..cycle.len() {
+            assert!(g.has_edge(cycle[i], cycle[(i + 1) % cycle.len()]));
+        }
+    }
+
+    #[test]
+    fn strongly_connected() {
+        let mut g = Graph::<char>::new_directed();
+        let ids: Vec<NodeId> = "abcdefgh"
+            .chars()
+            .map(|c| g.add_node_with_value(c))
+            .collect();
+        for edge in "ab bc be bf cd cg dc dh ea ef fg gf hd hg".split(' ') {
+            let edge: Vec<usize> = edge.bytes().map(|b| (b - b'a') as usize).collect();
+            g.add_edge(ids[edge[0]], ids[edge[1]]);
+        }
+
+        let sccs = g.strongly_connected_components();
+        let values = |c: &[NodeId]| -> String { c.iter().map(|n| g.nodes[n.0].value).collect() };
+        let found: Vec<String> = sccs.components.iter().map(|c| values(c)).collect();
+        assert_eq!(found, vec!["abe", "cdh", "fg"]);
+        assert!(sccs.same_component(&ids[2], &ids[7]));
+        assert!(!sccs.same_component(&ids[0], &ids[2]));
+
+        let dag = g.condensation();
+        assert_eq!(dag.nodes.len(), 3);
+        assert_eq!(dag.nodes[1].value, vec![ids[2], ids[3], ids[7]]);
+        let edges: Vec<(NodeId, NodeId)> = dag.edges.keys().copied().collect();
+        assert_eq!(
+            edges,
+            vec![
+                (NodeId(0), NodeId(1)),
+                (NodeId(0), NodeId(2)),
+                (NodeId(1), NodeId(2))
+            ]
+        );
+        assert!(dag.find_cycle().is_none());
+
+        let mut g = Graph::<char>::new_directed();
+        let ids: Vec<NodeId> = "abcd".chars().map(|c| g.add_node_with_value(c)).collect();
+        for (a, b) in [(2, 1), (1, 0), (3, 3)] {
+            g.add_edge(ids[a], ids[b]);
+        }
+        let sccs = g.strongly_connected_components();
+        assert_eq!(sccs.sizes(), vec![1, 1, 1, 1]);
+        assert!(sccs.membership[&ids[2]] < sccs.membership[&ids[1]]);
+        assert!(sccs.membership[&ids[1]] < sccs.membership[&ids[0]]);
+        assert_eq!(g.condensation().edges.len(), 2);
+    }
+
+    #[test]
+    fn cycles() {
+        // Rings of 3 and 4 sharing node 0, and a ring of 5 off to one side
+        let mut g = Graph::<usize>::new_directed();
+        let ids: Vec<NodeId> = (0..11).map(|i| g.add_node_with_value(i)).collect();
+        for (a, b) in [(0, 1), (1, 2), (2, 0), (0, 3), (3, 4), (4, 5), (5, 0)] {
+            g.add_edge(ids[a], ids[b]);
+        }
+        for (a, b) in [(2, 6), (6, 7), (7, 8), (8, 9), (9, 10), (10, 6)] {
+            g.add_edge(ids[a], ids[b]);
+        }
+        let mut cycles = g.cycles_through(ids[0]);
+        cycles.sort();
+        assert_eq!(
+            cycles,
+            vec![
+                vec![ids[0], ids[1], ids[2]],
+                vec![ids[0], ids[3], ids[4], ids[5]]
+            ]
+        );
+        assert_eq!(g.cycle_lengths(ids[0]), vec![3, 4]);
+        assert_eq!(g.cycle_lengths(ids[8]), vec![5]);
+        assert_eq!(sync_period(g.cycle_lengths(ids[0])), 12);
+
+        // A shortcut across the ring of 4 makes a ring of 3 too
+        g.add_edge(ids[3], ids[5]);
+        assert_eq!(g.cycle_lengths(ids[0]), vec![3, 4]);
+        assert_eq!(g.cycles_through(ids[0]).len(), 3);
+        assert!(g.cycles_through(ids[6]).iter().all(|c| c.len() == 5));
+        g.remove_edge(ids[10], ids[6]);
+        assert!(g.cycles_through(ids[6]).is_empty());
+
+        assert_eq!(sync_period([3733_u64, 3793, 3917, 4057]), 225008752522361);
+        assert_eq!(sync_period([4, 6, 10]), 60);
+        assert_eq!(sync_period(Vec::<u32>::new()), 1);
+    }
+
+    #[test]
+    fn parsing() {
+        let g = Graph::<&str>::from_edge_list(
+            "start-A\nstart-b\nA-c\n\nA-b\nb-d\nA-end\nb-end\n",
+            &["-"],
+            false,
+        )
+        .unwrap();
+        assert_eq!(g.nodes.len(), 6);
+        assert_eq!(g.edges.len(), 7);
+        let (a, b) = (
+            g.get_id_from_value(&"A").unwrap(),
+            g.get_id_from_value(&"b").unwrap(),
+        );
+        assert!(g.has_edge(b, a));
+
+        let orbits = "COM)B\nB)C\nC)D\nD)E\nE)F\nB)G\nG)H\nD)I\nE)J\nJ)K\nK)L";
+        let g = Graph::<String>::from_edge_list(orbits, &[")"], true).unwrap();
+        let com = g.get_id_from_value(&"COM".to_string()).unwrap();
+        let total: usize = Bfs::new(&com, &g).map(|v| v.depth).sum();
+        assert_eq!(total, 42);
+
+        // A mix of separators, with the longer one winning where they overlap
+        let g = Graph::<&str>::from_edge_list("a->b\nb-c\nc)d", &["-", "->", ")"], true).unwrap();
+        assert_eq!(
+            g.get_node_values(&g.get_node_ids()),
+            vec![Some("a"), Some("b"), Some("c"), Some("d")]
+        );
+        assert_eq!(g.edges.len(), 3);
+        let err = Graph::<&str>::from_edge_list("a-b)c", &["-", ")"], true).unwrap_err();
+        assert_eq!(err.line, 1);
+
+        let g = Graph::<&str>::from_adjacency_list(
+            "jqt: rhn xhk nvd\nrsh: frs pzl lsr\nxhk: hfx",
+            ":",
+            &[' '],
+            false,
+        )
+        .unwrap();
+        assert_eq!((g.nodes.len(), g.edges.len()), (9, 7));
+
+        let g = Graph::<&str, u32>::from_adjacency_list(
+            "AA -> BB, CC\nBB -> CC\nCC ->\nDD -> AA",
+            "->",
+            &[',', ' '],
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            g.get_node_values(&g.get_node_ids()),
+            vec![Some("AA"), Some("BB"), Some("CC"), Some("DD")]
+        );
+        assert_eq!(g.out_degree(NodeId(0)), 2);
+        assert_eq!(g.out_degree(NodeId(2)), 0);
+        assert_eq!(g.get_edge_data(NodeId(3), NodeId(0)), Some(&0));
+
+        let err = Graph::<&str>::from_edge_list("a-b\nb-c\nc d\n", &["-"], false).unwrap_err();
+        assert_eq!(err.line, 3);
+        assert_eq!(err.to_string(), "line 3: no separator from [\"-\"]");
+        let err = Graph::<&str>::from_edge_list("a-b\n\nb-\n", &["-"], false).unwrap_err();
+        assert_eq!(
+            err,
+            ParseError {
+                line: 3,
+                message: "missing second node".to_string()
+            }
+        );
+        let err = Graph::<&str>::from_edge_list("a-b\na-b-c\n", &["-"], false).unwrap_err();
+        assert_eq!(err.to_string(), "line 2: more than one separator");
+        let err = Graph::<&str>::from_adjacency_list("a: b\n: c", ":", &[' '], false).unwrap_err();
+        assert_eq!(err.line, 2);
+    }
+
+    #[test]
+    fn export() {
+        let mut g = Graph::<&str, u32>::new_directed_with_edge_data();
+        let ids: Vec<NodeId> = ["start", "mid", "say \"end\""]
+            .iter()
+            .map(|v| g.add_node_with_value(*v))
+            .collect();
+        g.add_edge_with_data(ids[0], ids[1], 3);
+        g.add_edge_with_data(ids[1], ids[2], 4);
+        g.add_edge_with_data(ids[0], ids[2], 9);
+
+        let mut out = vec![];
+        g.write_dot_weighted(&mut out, &[ids[0], ids[1]]).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "digraph {
+    n0 [label=\"start\", color=red, penwidth=2];
+    n1 [label=\"mid\", color=red, penwidth=2];
+    n2 [label=\"say \\\"end\\\"\"];
+    n0 -> n1 [label=\"3\", color=red, penwidth=2];
+    n0 -> n2 [label=\"9\"];
+    n1 -> n2 [label=\"4\"];
+}
+"
+        );
+
+        let mut out = vec![];
+        g.write_mermaid_weighted(&mut out, &[ids[1], ids[2]])
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "graph LR
+    n0[\"start\"]
+    n1[\"mid\"]
+    n2[\"say #quot;end#quot;\"]
+    n0 -->|\"3\"| n1
+    n0 -->|\"9\"| n2
+    n1 -->|\"4\"| n2
+    style n1 stroke:red,stroke-width:3px
+    style n2 stroke:red,stroke-width:3px
+    linkStyle 2 stroke:red,stroke-width:3px
+"
+        );
+
+        // Undirected paths can run against the way edges were added
+        let mut g = Graph::<char>::new();
+        let ids: Vec<NodeId> = "ab".chars().map(|c| g.add_node_with_value(c)).collect();
+        g.add_edge(ids[0], ids[1]);
+        let mut out = vec![];
+        g.write_dot(&mut out, &[ids[1], ids[0]]).unwrap();
+        let dot = String::from_utf8(out).unwrap();
+        assert!(dot.starts_with("graph {\n"));
+        assert!(dot.contains("    n0 -- n1 [color=red, penwidth=2];\n"));
+        let mut out = vec![];
+        g.write_mermaid(&mut out, &[]).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "graph LR\n    n0[\"a\"]\n    n1[\"b\"]\n    n0 --- n1\n"
+        );
+    }
+
+    #[test]
+    fn cliques() {
+        let input = "kh-tc qp-kh de-cg ka-co yn-aq qp-ub cg-tb vc-aq tb-ka wh-tc yn-cg kh-ub \
+                     ta-co de-co tc-td tb-wq wh-td ta-ka td-qp aq-cg wq-ub ub-vc de-ta wq-aq \
+                     wq-vc wh-yn ka-de kh-ta co-tc wh-qp tb-vc td-yn";
+        let mut g = Graph::<&str>::new();
+        for pair in input.split_whitespace() {
+            let ids: Vec<NodeId> = pair
+                .split('-')
+                .map(|v| {
+                    g.get_id_from_value(&v)
+                        .unwrap_or_else(|| g.add_node_with_value(v))
+                })
+                .collect();
+            g.add_edge(ids[0], ids[1]);
+        }
+
+        assert_eq!(g.triangles(|_, _, _| true).len(), 12);
+        let t = |v: &&str| v.starts_with('t');
+        assert_eq!(g.triangles(|a, b, c| t(a) || t(b) || t(c)).len(), 7);
+
+        let mut party: Vec<&str> = g
+            .get_node_values(&g.maximum_clique())
+            .into_iter()
+            .flatten()
+            .collect();
+        party.sort();
+        assert_eq!(party.join(","), "co,de,ka,ta");
+        let cliques = g.maximal_cliques();
+        assert!(cliques.iter().all(|c| c.len() <= 4));
+        assert_eq!(cliques.iter().filter(|c| c.len() == 4).count(), 1);
+    }
+
+    #[test]
+    fn coloring() {
+        // Crown graph, where going in id order alternates between the halves
+        let mut g = Graph::<(u8, usize)>::new();
+        for i in 0..4 {
+            g.add_node_with_value((0, i));
+            g.add_node_with_value((1, i));
+        }
+        for i in 0..4 {
+            for j in 0..4 {
+                if i != j {
+                    g.add_edge(NodeId(2 * i), NodeId(2 * j + 1));
+                }
+            }
+        }
+
+        let proper = |colors: &[usize]| g.edges.keys().all(|&(a, b)| colors[a.0] != colors[b.0]);
+        let greedy = g.greedy_coloring();
+        assert!(proper(&greedy));
+        assert_eq!(greedy.iter().max(), Some(&3));
+        let dsatur = g.dsatur_coloring();
+        assert!(proper(&dsatur));
+        assert_eq!(dsatur.iter().max(), Some(&1));
+
+        // Odd cycle needs a third color
+        let mut g = Graph::<usize>::new();
+        let ids: Vec<NodeId> = (0..5).map(|v| g.add_node_with_value(v)).collect();
+        for i in 0..5 {
+            g.add_edge(ids[i], ids[(i + 1) % 5]);
+        }
+        assert_eq!(g.dsatur_coloring().iter().max(), Some(&2));
+        assert_eq!(g.maximum_clique().len(), 2);
+        assert!(g.triangles(|_, _, _| true).is_empty());
+    }
+
+    #[test]
+    fn flow() {
+        let mut g: Graph<&str, u32> = Graph::new_directed_with_edge_data();
+        let ids: Vec<NodeId> = ["s", "v1", "v2", "v3", "v4", "t"]
+            .iter()
+            .map(|v| g.add_node_with_value(*v))
+            .collect();
+        for (a, b, c) in [
+            (0, 1, 16),
+            (0, 2, 13),
+            (2, 1, 4),
+            (1, 3, 12),
+            (3, 2, 9),
+            (2, 4, 14),
+            (4, 3, 7),
+            (3, 5, 20),
+            (4, 5, 4),
+        ] {
+            g.add_edge_with_data(ids[a], ids[b], c);
+        }
+        let flow = g.max_flow(ids[0], ids[5], |c| *c);
+        assert_eq!(flow.value, 23);
+        assert_eq!(flow.cut.value, 23);
+        assert_eq!(flow.cut.side_a, HashSet::from(["s", "v1", "v2", "v4"]));
+        assert_eq!(
+            flow.cut.edges,
+            vec![(ids[1], ids[3]), (ids[4], ids[3]), (ids[4], ids[5])]
+        );
+        let into_sink: u32 = flow
+            .flows
+            .iter()
+            .filter(|((_, b), _)| *b == ids[5])
+            .map(|(_, f)| f)
+            .sum();
+        assert_eq!(into_sink, 23);
+        for (edge, f) in &flow.flows {
+            assert!(f <= g.get_edge_data(edge.0, edge.1).unwrap());
+        }
+
+        // Nothing flows backwards along directed edges
+        assert_eq!(g.max_flow(ids[5], ids[0], |c| *c).value, 0);
+
+        // Effectively unlimited capacities out of the source
+        let unlimited = |c: &u32| if *c == 16 || *c == 13 { u32::MAX } else { *c };
+        assert_eq!(g.max_flow(ids[0], ids[5], unlimited).value, 23);
+    }
+
+    #[test]
+    #[should_panic(expected = "source and sink")]
+    fn flow_to_self() {
+        let mut g: Graph<&str, u32> = Graph::new_directed_with_edge_data();
+        let s = g.add_node_with_value("s");
+        g.max_flow(s, s, |c| *c);
+    }
+
+    #[test]
+    fn cuts() {
+        let input = "jqt: rhn xhk nvd
+rsh: frs pzl lsr
+xhk: hfx
+cmg: qnr nvd lhk bvb
+rhn: xhk bvb hfx
+bvb: xhk hfx
+pzl: lsr hfx nvd
+qnr: nvd
+ntq: jqt hfx bvb xhk
+nvd: lhk
+lsr: lhk
+rzs: qnr cmg lsr rsh
+frs: qnr lhk lsr";
+        let mut g = Graph::<&str>::new();
+        let id = |g: &mut Graph<&str>, v| {
+            g.get_id_from_value(&v)
+                .unwrap_or_else(|| g.add_node_with_value(v))
+        };
+        for line in input.lines() {
+            let (from, rest) = line.split_once(": ").unwrap();
+            let a = id(&mut g, from);
+            for to in rest.split(' ') {
+                let b = id(&mut g, to);
+                g.add_edge(a, b);
+            }
+        }
+
+        let cut = g.min_cut(|_| 1_u32).unwrap();
+        assert_eq!(cut.value, 3);
+        assert_eq!(cut.side_a.len() * cut.side_b.len(), 54);
+        let mut wires: Vec<Vec<&str>> = cut
+            .edges
+            .iter()
+            .map(|&(a, b)| {
+                let mut wire = vec![g.nodes[a.0].value, g.nodes[b.0].value];
+                wire.sort();
+                wire
+            })
+            .collect();
+        wires.sort();
+        assert_eq!(
+            wires,
+            vec![vec!["bvb", "cmg"], vec!["hfx", "pzl"], vec!["jqt", "nvd"]]
+        );
+
+        let a = g.get_id_from_value(&"jqt").unwrap();
+        let b = g.get_id_from_value(&"cmg").unwrap();
+        let flow = g.max_flow(a, b, |_| 1_u32);
+        assert_eq!(flow.value, 3);
+        assert_eq!(flow.cut.side_a.len(), 6);
+
+        let mut g = Graph::<u8, u32>::with_edge_data();
+        g.add_node_with_value(0);
+        assert_eq!(g.min_cut(|w| *w), None);
+        g.add_node_with_value(1);
+        assert_eq!(g.min_cut(|w| *w).unwrap().value, 0);
+    }
+
+    #[test]
+    fn hiking() {
+        let map = [
+            "#.#####################",
+            "#.......#########...###",
+            "#######.#########.#.###",
+            "###.....#.>.>.###.#.###",
+            "###v#####.#v#.###.#.###",
+            "###.>...#.#.#.....#...#",
+            "###v###.#.#.#########.#",
+            "###...#.#.#.......#...#",
+            "#####.#.#.#######.#.###",
+            "#.....#.#.#.......#...#",
+            "#.#####.#.#.#########v#",
+            "#.#...#...#...###...>.#",
+            "#.#.#v#######v###.###v#",
+            "#...#.>.#...>.>.#.###.#",
+            "#####v#.#.###v#.#.###.#",
+            "#.....#...#...#.#.#...#",
+            "#.#########.###.#.#.###",
+            "#...###...#...#...#.###",
+            "###.###.#.###v#####v###",
+            "#...#...#.#.>.>.#.>.###",
+            "#.###.###.#.###.#.#v###",
+            "#.....###...###...#...#",
+            "#####################.#",
+        ];
+        let mut squares = [['#'; 23]; 23];
+        for (r, line) in map.iter().enumerate() {
+            for (c, ch) in line.chars().enumerate() {
+                squares[r][c] = ch;
+            }
+        }
+        let board = SizedBoard { squares };
+        let (start, end) = ((0, 1), (22, 21));
+
+        // Slopes can only be walked down
+        let slippery = board.connected(|from, to| {
+            let step = (to.0 as i64 - from.0 as i64, to.1 as i64 - from.1 as i64);
+            match board.squares[from.0][from.1] {
+                '.' => board.squares[to.0][to.1] != '#',
+                c => Direction::from_char(c).map(|d| d.offset()) == Some(step),
+            }
+        });
+        let junctions = contract_corridors(&slippery, [start, end]);
+        assert!(junctions.len() < 12);
+        assert_eq!(longest_path(&start, &end, &junctions).unwrap().0, 94);
+
+        let dry = board.connected(|_, to| board.squares[to.0][to.1] != '#');
+        let junctions = contract_corridors(&dry, [start, end]);
+        assert_eq!(junctions[&start], vec![((5, 3), 15)]);
+        let (cost, path) = longest_path(&start, &end, &junctions).unwrap();
+        assert_eq!(cost, 154);
+        assert_eq!((path[0], path[path.len() - 1]), (start, end));
+        assert_eq!(longest_path(&end, &(3, 3), &junctions), None);
+    }
+
+    fn valves() -> (Graph<&'static str>, HashMap<&'static str, u32>) {
+        let input = "Valve AA has flow rate=0; tunnels lead to valves DD, II, BB
+Valve BB has flow rate=13; tunnels lead to valves CC, AA
+Valve CC has flow rate=2; tunnels lead to valves DD, BB
+Valve DD has flow rate=20; tunnels lead to valves CC, AA, EE
+Valve EE has flow rate=3; tunnels lead to valves FF, DD
+Valve FF has flow rate=0; tunnels lead to valves EE, GG
+Valve GG has flow rate=0; tunnels lead to valves FF, HH
+Valve HH has flow rate=22; tunnel leads to valve GG
+Valve II has flow rate=0; tunnels lead to valves AA, JJ
+Valve JJ has flow rate=21; tunnel leads to valve II";
+        let mut g = Graph::new_directed();
+        let mut rates = HashMap::new();
+        for line in input.lines() {
+            let words: Vec<&str> = line.split([' ', '=', ';', ',']).collect();
+            rates.insert(words[1], words[5].parse().unwrap());
+            g.add_node_with_value(words[1]);
+        }
+        for line in input.lines() {
+            let words: Vec<&str> = line.split([' ', ',']).collect();
+            let from = g.get_id_from_value(&words[1]).unwrap();
+            for to in words[9..].iter().filter(|w| !w.is_empty()) {
+                let to = g.get_id_from_value(to).unwrap();
+                g.add_edge(from, to);
+            }
+        }
+        (g, rates)
+    }
+
+    #[test]
+    fn all_pairs() {
+        let (g, rates) = valves();
+        let id = |v| g.get_id_from_value(&v).unwrap();
+        let mut useful: Vec<NodeId> = vec![id("AA")];
+        useful.extend(
+            g.get_node_ids()
+                .into_iter()
+                .filter(|n| rates[g.nodes[n.0].value] > 0),
+        );
+
+        let bfs = all_pairs_bfs(useful.clone(), &g);
+        assert_eq!(bfs.len(), 7);
+        assert_eq!(bfs.distance(&id("AA"), &id("HH")), Some(5));
+        assert_eq!(bfs.distance(&id("JJ"), &id("HH")), Some(7));
+        assert_eq!(bfs.distance(&id("DD"), &id("DD")), Some(0));
+        assert_eq!(bfs.distance(&id("AA"), &id("FF")), None);
+
+        let fw = floyd_warshall(g.get_node_ids(), &Unweighted(&g));
+        assert_eq!(fw.len(), 10);
+        assert_eq!(fw.distance(&id("AA"), &id("FF")), Some(3));
+        assert_eq!(fw.subset(useful.clone()), bfs);
+
+        // Repeats are only counted once
+        useful.push(id("AA"));
+        useful.insert(1, id("HH"));
+        let bfs = all_pairs_bfs(useful.clone(), &g);
+        assert_eq!(bfs.len(), 7);
+        assert_eq!(bfs.distance(&id("AA"), &id("HH")), Some(5));
+        let fw = floyd_warshall(useful, &Unweighted(&g));
+        assert_eq!(fw.len(), 10);
+        assert_eq!(fw.distance(&id("HH"), &id("AA")), Some(5));
+
+        // One way streets, so some pairs can't be reached
+        let mut g = Graph::<char, u32>::new_directed_with_edge_data();
+        let ids: Vec<NodeId> = "abcd".chars().map(|c| g.add_node_with_value(c)).collect();
+        g.add_edge_with_data(ids[0], ids[1], 5);
+        g.add_edge_with_data(ids[1], ids[2], 1);
+        g.add_edge_with_data(ids[0], ids[2], 9);
+        g.add_edge_with_data(ids[2], ids[0], 2);
+        let fw = floyd_warshall([ids[3], ids[0]], &g);
+        assert_eq!(fw.nodes, vec![ids[3], ids[0], ids[1], ids[2]]);
+        assert_eq!(fw.distance(&ids[0], &ids[2]), Some(6));
+        assert_eq!(fw.distance(&ids[1], &ids[0]), Some(3));
+        assert_eq!(fw.distance(&ids[0], &ids[3]), None);
+        assert_eq!(fw.distances[0], vec![Some(0), None, None, None]);
+    }
+
+    #[test]
+    fn tours() {
+        let mut g = Graph::<&str, u32>::with_edge_data();
+        let ids: Vec<NodeId> = ["London", "Dublin", "Belfast"]
+            .iter()
+            .map(|v| g.add_node_with_value(*v))
+            .collect();
+        g.add_edge_with_data(ids[0], ids[1], 464);
+        g.add_edge_with_data(ids[0], ids[2], 518);
+        g.add_edge_with_data(ids[1], ids[2], 141);
+        let m = floyd_warshall(g.get_node_ids(), &g);
+        let (cost, path) = m.shortest_tour(None, false).unwrap();
+        assert_eq!((cost, path.len(), path[1]), (605, 3, ids[1]));
+        assert_eq!(m.longest_tour(None, false).unwrap().0, 982);
+        assert_eq!(m.shortest_tour(Some(&ids[1]), false).unwrap().0, 659);
+        let (cost, path) = m.shortest_tour(Some(&ids[1]), true).unwrap();
+        assert_eq!(cost, 1123);
+        assert_eq!((path.len(), path[0], path[3]), (4, ids[1], ids[1]));
+
+        // Can't get back from c
+        let mut g = Graph::<char, u32>::new_directed_with_edge_data();
+        let ids: Vec<NodeId> = "abc".chars().map(|c| g.add_node_with_value(c)).collect();
+        g.add_edge_with_data(ids[0], ids[1], 1);
+        g.add_edge_with_data(ids[1], ids[2], 1);
+        g.add_edge_with_data(ids[1], ids[0], 1);
+        let m = floyd_warshall(g.get_node_ids(), &g).subset([ids[0], ids[2]]);
+        assert_eq!(
+            m.shortest_tour(None, false),
+            Some((2, vec![ids[0], ids[2]]))
+        );
+        assert_eq!(m.shortest_tour(Some(&ids[2]), false), None);
+        assert_eq!(m.shortest_tour(None, true), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Too many nodes for held_karp")]
+    fn tour_too_big() {
+        let nodes: Vec<usize> = (0..21).collect();
+        let distances = vec![vec![Some(1_u32); 21]; 21];
+        DistanceMatrix::new(nodes, distances).longest_tour(None, false);
+    }
+
+    #[test]
+    fn rewards() {
+        let (g, rates) = valves();
+        let start = g.get_id_from_value(&"AA").unwrap();
+        let mut useful: Vec<NodeId> = vec![start];
+        useful.extend(
+            g.get_node_ids()
+                .into_iter()
+                .filter(|n| rates[g.nodes[n.0].value] > 0),
+        );
+        let m = all_pairs_bfs(useful, &g);
+        let pressure =
+            |n: &NodeId, remaining: usize| rates[g.nodes[n.0].value] as usize * remaining;
+        assert_eq!(m.max_reward(&start, 30, 1, pressure), 1651);
+        assert_eq!(m.max_reward_pair(&start, 26, 1, pressure), 1707);
+        assert_eq!(m.max_reward(&start, 1, 1, pressure), 0);
+    }
+
+    #[test]
+    fn state_search() {
+        // Collect every key, doors only opening once their key is held
+        let maze = "########################
+#f.D.E.e.C.b.A.@.a.B.c.#
+######################.#
+#d.....................#
+########################";
+        let chars: Vec<Vec<char>> = maze.lines().map(|l| l.chars().collect()).collect();
+        let all_keys = chars
+            .iter()
+            .flatten()
+            .filter(|c| c.is_ascii_lowercase())
+            .count();
+        let successors = |&(pos, keys): &((i64, i64), u32)| {
+            get_orthogonal_neighbors(pos)
+                .into_iter()
+                .filter_map(|n| {
+                    let c = chars[n.0 as usize][n.1 as usize];
+                    if c == '#' || (c.is_ascii_uppercase() && keys & (1 << (c as u8 - b'A')) == 0) {
+                        None
+                    } else if c.is_ascii_lowercase() {
+                        Some(((n, keys | 1 << (c as u8 - b'a')), 1))
+                    } else {
+                        Some(((n, keys), 1))
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+        let goal = |s: &((i64, i64), u32)| s.1.count_ones() as usize == all_keys;
+        let start = ((1, 15), 0);
+
+        let path = Search::new([start], successors, goal).bfs().unwrap();
+        assert_eq!(path.len(), 87);
+        let (cost, path) = Search::new([start], successors, goal).run().unwrap();
+        assert_eq!((cost, path[0]), (86, start));
+        let remaining = |s: &((i64, i64), u32)| (all_keys - s.1.count_ones() as usize) as u32;
+        let search = Search::new([start], successors, goal).heuristic(remaining);
+        assert_eq!(search.run().unwrap().0, 86);
+
+        // Only the keys held matter, not where they were picked up
+        let counted = |&(pos, keys, n): &((i64, i64), u32, u32)| {
+            successors(&(pos, keys))
+                .into_iter()
+                .map(|((pos, keys), c)| ((pos, keys, n + 1), c))
+                .collect::<Vec<_>>()
+        };
+        let search = Search::new([((1, 15), 0, 0)], counted, |s| goal(&(s.0, s.1)));
+        let (cost, path) = search.key(|s| (s.0, s.1)).run().unwrap();
+        assert_eq!((cost, path.last().unwrap().2), (86, 86));
+
+        // Several starts, with the nearest winning
+        let starts = [((1, 1), u32::MAX), ((3, 22), u32::MAX)];
+        let search = Search::new(starts, successors, |s| s.0 == (1, 20));
+        assert_eq!(search.bfs().unwrap()[0], ((3, 22), u32::MAX));
+        let search = Search::new([start], successors, |s| s.0 == (0, 0));
+        assert_eq!(search.run(), None);
+    }
+
+    #[test]
+    fn traversal() {
+        let mut g = Graph::<char>::new_directed();
+        let ids: Vec<NodeId> = "abcdef".chars().map(|c| g.add_node_with_value(c)).collect();
+        for (a, b) in [(0, 1), (0, 2), (1, 3), (2, 3), (3, 4), (5, 0)] {
+            g.add_edge(ids[a], ids[b]);
+        }
+
+        let visits: Vec<Visit<NodeId>> = Bfs::new(&ids[0], &g).collect();
+        let order: Vec<NodeId> = visits.iter().map(|v| v.node).collect();
+        assert_eq!(order, vec![ids[0], ids[1], ids[2], ids[3], ids[4]]);
+        assert_eq!(
+            visits[3],
+            Visit {
+                node: ids[3],
+                depth: 2,
+                parent: Some(ids[1])
+            }
+        );
+        assert_eq!(visits[0].parent, None);
+
+        let mut bfs = Bfs::new(&ids[0], &g);
+        let near: Vec<NodeId> = bfs
+            .by_ref()
+            .take_while(|v| v.depth <= 1)
+            .map(|v| v.node)
+            .collect();
+        assert_eq!(near, vec![ids[0], ids[1], ids[2]]);
+        // Finding d, the first node too far away, also found e
+        assert_eq!(bfs.visited().len(), 5);
+
+        let visits: Vec<Visit<NodeId>> = Dfs::new(&ids[0], &g).collect();
+        let order: Vec<NodeId> = visits.iter().map(|v| v.node).collect();
+        assert_eq!(order, vec![ids[0], ids[1], ids[3], ids[4], ids[2]]);
+        assert_eq!(visits[3].depth, 3);
+        assert_eq!(visits[4].parent, Some(ids[0]));
+
+        let mut dfs = Dfs::from_starts([ids[3], ids[5]], &g);
+        assert_eq!(dfs.nth(2).map(|v| (v.node, v.depth)), Some((ids[5], 0)));
+        assert_eq!(dfs.visited().len(), 3);
+        assert_eq!(dfs.count(), 3);
+
+        // Squares of a garden reachable within 3 steps
+        let garden: HashSet<(usize, usize)> = (0..5)
+            .flat_map(|r| (0..5).map(move |c| (r, c)))
+            .filter(|&(r, c)| (r, c) != (1, 2) && (r, c) != (2, 1))
+            .collect();
+        let within = Bfs::new(&(2, 2), &garden)
+            .take_while(|v| v.depth <= 3)
+            .count();
+        assert_eq!(within, 14);
+        let mut everywhere = Bfs::from_starts([(0, 0), (4, 4)], &garden);
+        assert_eq!(
+            everywhere.find(|v| v.depth == 4).map(|v| v.node),
+            Some((0, 4))
+        );
+    }
+
+    #[test]
+    fn path_finding() {
+        let mut graph: HashSet<(usize, usize)> = HashSet::new();
+        for i in 0..20 {
+            for j in 0..20 {
+                graph.insert((i, j));
+            }
+        }
+        let path = shortest_path(&(12, 16), &(15, 7), &graph).unwrap();
+        assert_eq!(path.len() - 1, 12);
+        let path = shortest_path(&(2, 16), &(15, 17), &graph).unwrap();
+        assert_eq!(path.len() - 1, 14);
+    }
+
+    struct Costs(Vec<Vec<u32>>);
+
+    impl WeightedConnected for Costs {
+        type Item = (usize, usize);
+        type Cost = u32;
+        fn get_weighted_neighbors(&self, node: &Self::Item) -> Vec<(Self::Item, u32)> {
+            let mut output = vec![];
+            let (r, c) = *node;
+            let mut candidates = vec![(r + 1, c), (r, c + 1)];
+            if r > 0 {
+                candidates.push((r - 1, c));
+            }
+            if c > 0 {
+                candidates.push((r, c - 1));
+            }
+            for (nr, nc) in candidates {
+                if let Some(cost) = self.0.get(nr).and_then(|row| row.get(nc)) {
+                    output.push(((nr, nc), *cost));
+                }
+            }
+            output
+        }
+    }
+
+    fn costs(input: &str) -> Costs {
+        Costs(
+            input
+                .lines()
+                .map(|line| line.chars().map(|c| c.to_digit(10).unwrap()).collect())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn weighted_path_finding() {
+        let graph = costs(
+            "1163751742\n1381373672\n2136511328\n3694931569\n7463417111\n\
+             1319128137\n1359912421\n3125421639\n1293138521\n2311944581",
+        );
+        let (cost, path) = dijkstra(&(0, 0), &(9, 9), &graph).unwrap();
+        assert_eq!(cost, 40);
+        assert_eq!(path[0], (0, 0));
+        assert_eq!(path[path.len() - 1], (9, 9));
+        let total: u32 = path.iter().skip(1).map(|&(r, c)| graph.0[r][c]).sum();
+        assert_eq!(total, cost);
+
+        // Cheap detour beats the direct route
+        let graph = costs("191\n111");
+        let (cost, path) = dijkstra(&(0, 0), &(0, 2), &graph).unwrap();
+        assert_eq!(cost, 4);
+        assert_eq!(path, vec![(0, 0), (1, 0), (1, 1), (1, 2), (0, 2)]);
+        assert_eq!(dijkstra(&(0, 0), &(5, 5), &graph), None);
+        assert_eq!(dijkstra(&(0, 0), &(0, 0), &graph), Some((0, vec![(0, 0)])));
+    }
+
+    #[test]
+    fn all_shortest_path_counts() {
+        let mut open: HashSet<(usize, usize)> = HashSet::new();
+        for i in 0..40 {
+            for j in 0..40 {
+                open.insert((i, j));
+            }
+        }
+        let graph = Unweighted(&open);
+        let paths = all_shortest_paths(&(0, 0), &graph);
+        assert_eq!(paths.distances[&(39, 39)], 78);
+        assert_eq!(paths.predecessors[&(39, 39)].len(), 2);
+        assert_eq!(paths.path_count(&(2, 2)), BigUint::from(6_u32));
+        // 78 choose 39 is too big for a u64
+        let binomial = (1..=39_u32).fold(BigUint::one(), |acc, k| acc * (39 + k) / k);
+        assert_eq!(paths.path_count(&(39, 39)), binomial);
+        assert_eq!(paths.nodes_on_paths(&(2, 3)).len(), 12);
+        assert_eq!(paths.path_count(&(40, 40)), BigUint::zero());
+        assert!(paths.nodes_on_paths(&(40, 40)).is_empty());
+
+        // Going down the left edge through the 2 costs more
+        let graph = costs("1111\n1191\n2111");
+        let paths = all_shortest_paths(&(0, 0), &graph);
+        assert_eq!(paths.distances[&(1, 3)], 4);
+        assert_eq!(paths.path_count(&(1, 3)), BigUint::from(1_u32));
+        assert_eq!(paths.distances[&(2, 3)], 5);
+        assert_eq!(paths.path_count(&(2, 3)), BigUint::from(3_u32));
+        let on_paths = paths.nodes_on_paths(&(2, 3));
+        assert_eq!(on_paths.len(), 10);
+        assert!(!on_paths.contains(&(2, 0)));
+    }
+
+    #[test]
+    fn astar_path_finding() {
+        let graph = costs(
+            "1163751742\n1381373672\n2136511328\n3694931569\n7463417111\n\
+             1319128137\n1359912421\n3125421639\n1293138521\n2311944581",
+        );
+        // Every step costs at least 1, so distance is a lower bound
+        let h = |p: &(usize, usize)| manhattan((p.0 as i64, p.1 as i64), (9, 9)) as u32;
+        let (cost, path) = astar(&(0, 0), &(9, 9), &graph, h).unwrap();
+        assert_eq!(cost, 40);
+        assert_eq!(Some((cost, path)), dijkstra(&(0, 0), &(9, 9), &graph));
+
+        let mut open: HashSet<(usize, usize)> = HashSet::new();
+        for i in 0..20 {
+            for j in 0..20 {
+                if i != 10 || j == 19 {
+                    open.insert((i, j));
+                }
+            }
+        }
+        let h = |p: &(usize, usize)| chebyshev((p.0 as i64, p.1 as i64), (15, 7)) as usize;
+        let (cost, path) = astar(&(2, 7), &(15, 7), &Unweighted(&open), h).unwrap();
+        assert_eq!(cost, 13 + 2 * 12);
+        assert_eq!(path.len() - 1, cost);
+        assert_eq!(
+            shortest_path(&(2, 7), &(15, 7), &open).unwrap().len(),
+            path.len()
+        );
     }
 }
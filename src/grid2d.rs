@@ -1,3 +1,8 @@
+use crate::graph::Connected;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
 pub fn get_neighbors(pt: (i64, i64)) -> Vec<(i64, i64)> {
     vec![
         (pt.0 - 1, pt.1 - 1),
@@ -12,7 +17,46 @@ pub fn get_neighbors(pt: (i64, i64)) -> Vec<(i64, i64)> {
     ]
 }
 
-#[derive(Copy, Clone, Debug)]
+/// The 4 points sharing an edge with `pt`, i.e. the squares reachable with a
+/// single up/down/left/right step.
+pub fn get_orthogonal_neighbors(pt: (i64, i64)) -> Vec<(i64, i64)> {
+    vec![
+        (pt.0 - 1, pt.1),
+        (pt.0, pt.1 - 1),
+        (pt.0, pt.1 + 1),
+        (pt.0 + 1, pt.1),
+    ]
+}
+
+/// Which of the surrounding squares count as neighbors.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Neighborhood {
+    /// The 4 orthogonally adjacent squares.
+    VonNeumann,
+    /// All 8 surrounding squares, as in `get_neighbors` minus `pt` itself.
+    Moore,
+}
+
+impl Neighborhood {
+    pub fn neighbors(&self, pt: (i64, i64)) -> Vec<(i64, i64)> {
+        match self {
+            Neighborhood::VonNeumann => get_orthogonal_neighbors(pt),
+            Neighborhood::Moore => get_neighbors(pt).into_iter().filter(|&n| n != pt).collect(),
+        }
+    }
+}
+
+/// Number of orthogonal steps between two points.
+pub fn manhattan(a: (i64, i64), b: (i64, i64)) -> i64 {
+    (a.0 - b.0).abs() + (a.1 - b.1).abs()
+}
+
+/// Number of steps between two points when diagonal steps are allowed.
+pub fn chebyshev(a: (i64, i64), b: (i64, i64)) -> i64 {
+    (a.0 - b.0).abs().max((a.1 - b.1).abs())
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct SizedBoard<T, const R: usize, const C: usize> {
     pub squares: [[T; C]; R],
 }
@@ -28,4 +72,936 @@ where
             .map(|pt| self.squares[pt.0 as usize][pt.1 as usize].clone())
             .collect()
     }
+
+    /// View the board as a `graph::Connected` where you can step orthogonally
+    /// from one square to another whenever `passable(from, to)` is true.
+    pub fn connected<F>(&self, passable: F) -> BoardGraph<'_, T, F, R, C>
+    where
+        F: Fn((usize, usize), (usize, usize)) -> bool,
+    {
+        BoardGraph {
+            board: self,
+            passable,
+        }
+    }
+
+    /// Label the regions of orthogonally connected equal squares, see
+    /// `label_regions`.
+    pub fn regions(&self) -> Vec<Region>
+    where
+        T: PartialEq,
+    {
+        let points = (0..R as i64).flat_map(|r| (0..C as i64).map(move |c| (r, c)));
+        label_regions(points, |a, b| {
+            self.squares[a.0 as usize][a.1 as usize] == self.squares[b.0 as usize][b.1 as usize]
+        })
+    }
+}
+
+/// `graph::Connected` adaptor over a `SizedBoard`, see
+/// `SizedBoard::connected`.
+pub struct BoardGraph<'a, T, F, const R: usize, const C: usize> {
+    /// The board being walked over.
+    pub board: &'a SizedBoard<T, R, C>,
+    /// Whether a step from the first square to the second is allowed.
+    pub passable: F,
+}
+
+impl<T, F, const R: usize, const C: usize> Connected for BoardGraph<'_, T, F, R, C>
+where
+    F: Fn((usize, usize), (usize, usize)) -> bool,
+{
+    type Item = (usize, usize);
+
+    fn get_neighbors(&self, node: &Self::Item) -> Vec<Self::Item> {
+        get_orthogonal_neighbors((node.0 as i64, node.1 as i64))
+            .into_iter()
+            .filter(|pt| pt.0 >= 0 && pt.1 >= 0 && pt.0 < R as i64 && pt.1 < C as i64)
+            .map(|pt| (pt.0 as usize, pt.1 as usize))
+            .filter(|&pt| (self.passable)(*node, pt))
+            .collect()
+    }
+}
+
+/// A grid a cellular automaton can be run over.
+pub trait CellGrid {
+    type Cell;
+
+    /// Apply `rule` to every cell of `self`, writing the results into `next`.
+    /// The rule is handed the current cell and the values of its neighbors.
+    fn step_into<F>(&self, neighborhood: Neighborhood, rule: &F, next: &mut Self)
+    where
+        F: Fn(&Self::Cell, &[Self::Cell]) -> Self::Cell;
+
+    /// Hash of the grid contents, used to spot repeated states.
+    fn fingerprint(&self) -> u64;
+
+    /// Put the grid in the same form `step_into` leaves it in, so that
+    /// equal contents compare equal. Does nothing by default.
+    fn normalize(&mut self) {}
+}
+
+/// Dense grid. Neighbors falling off the edge of the board are left out.
+impl<T, const R: usize, const C: usize> CellGrid for SizedBoard<T, R, C>
+where
+    T: Clone + Hash,
+{
+    type Cell = T;
+
+    fn step_into<F>(&self, neighborhood: Neighborhood, rule: &F, next: &mut Self)
+    where
+        F: Fn(&T, &[T]) -> T,
+    {
+        let mut values = vec![];
+        for r in 0..R {
+            for c in 0..C {
+                values.clear();
+                for pt in neighborhood.neighbors((r as i64, c as i64)) {
+                    if pt.0 >= 0 && pt.1 >= 0 && pt.0 < R as i64 && pt.1 < C as i64 {
+                        values.push(self.squares[pt.0 as usize][pt.1 as usize].clone());
+                    }
+                }
+                next.squares[r][c] = rule(&self.squares[r][c], &values);
+            }
+        }
+    }
+
+    fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.squares.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// Sparse, unbounded grid. Missing points hold `T::default()`, and only
+/// points that end up with a non-default value are stored.
+impl<T> CellGrid for HashMap<(i64, i64), T>
+where
+    T: Clone + Default + PartialEq + Hash,
+{
+    type Cell = T;
+
+    fn step_into<F>(&self, neighborhood: Neighborhood, rule: &F, next: &mut Self)
+    where
+        F: Fn(&T, &[T]) -> T,
+    {
+        // Only stored points and their neighbors can change
+        let mut candidates: HashSet<(i64, i64)> = HashSet::new();
+        for pt in self.keys() {
+            candidates.insert(*pt);
+            candidates.extend(neighborhood.neighbors(*pt));
+        }
+
+        next.clear();
+        let empty = T::default();
+        let mut values = vec![];
+        for pt in candidates {
+            values.clear();
+            for n in neighborhood.neighbors(pt) {
+                values.push(self.get(&n).unwrap_or(&empty).clone());
+            }
+            let value = rule(self.get(&pt).unwrap_or(&empty), &values);
+            if value != empty {
+                next.insert(pt, value);
+            }
+        }
+    }
+
+    fn fingerprint(&self) -> u64 {
+        // Sum the entry hashes so iteration order doesn't matter
+        let mut output: u64 = 0;
+        for entry in self.iter().filter(|(_, v)| **v != T::default()) {
+            let mut hasher = DefaultHasher::new();
+            entry.hash(&mut hasher);
+            output = output.wrapping_add(hasher.finish());
+        }
+        output
+    }
+
+    /// Drop stored default values, so a grid parsed with every point filled
+    /// in matches the sparse grids `step_into` makes.
+    fn normalize(&mut self) {
+        let empty = T::default();
+        self.retain(|_, v| *v != empty);
+    }
+}
+
+/// Runs a cellular automaton, double buffering so each step writes into a
+/// second grid rather than allocating a new one.
+pub struct Automaton<G, F> {
+    pub grid: G,
+    pub generation: usize,
+    neighborhood: Neighborhood,
+    rule: F,
+    buffer: G,
+}
+
+impl<G, F> Automaton<G, F>
+where
+    G: CellGrid + Clone + PartialEq,
+    F: Fn(&G::Cell, &[G::Cell]) -> G::Cell,
+{
+    pub fn new(mut grid: G, neighborhood: Neighborhood, rule: F) -> Self {
+        grid.normalize();
+        let buffer = grid.clone();
+        Self {
+            grid,
+            generation: 0,
+            neighborhood,
+            rule,
+            buffer,
+        }
+    }
+
+    /// Advance one generation. Returns true if the grid changed.
+    pub fn step(&mut self) -> bool {
+        self.grid
+            .step_into(self.neighborhood, &self.rule, &mut self.buffer);
+        std::mem::swap(&mut self.grid, &mut self.buffer);
+        self.generation += 1;
+        self.grid != self.buffer
+    }
+
+    pub fn run(&mut self, steps: usize) {
+        for _ in 0..steps {
+            self.step();
+        }
+    }
+
+    /// Step until the grid stops changing, returning the generation the
+    /// fixed point was first reached at. Never returns if the automaton
+    /// oscillates, use `run_until_repeat` for that.
+    pub fn run_until_stable(&mut self) -> usize {
+        while self.step() {}
+        self.generation - 1
+    }
+
+    /// Step until a previously seen state comes around again. Returns
+    /// `(start, period)` where `start` is the first generation of the cycle.
+    /// The grid is left at generation `start + period`.
+    pub fn run_until_repeat(&mut self) -> (usize, usize) {
+        let mut seen: HashMap<u64, Vec<usize>> = HashMap::new();
+        let mut history: Vec<G> = vec![];
+        let first = self.generation;
+        loop {
+            let key = self.grid.fingerprint();
+            let candidates = seen.entry(key).or_default();
+            for &generation in candidates.iter() {
+                if history[generation - first] == self.grid {
+                    return (generation, self.generation - generation);
+                }
+            }
+            candidates.push(self.generation);
+            history.push(self.grid.clone());
+            self.step();
+        }
+    }
+}
+
+/// Compass direction on a grid. Offsets are `(row, col)` with rows counting
+/// down the page, matching `SizedBoard::squares[row][col]`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    /// Clockwise from `North`.
+    pub const ALL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    /// Parse an arrow (`^>v<`), compass (`NESW`) or `UDLR` character.
+    pub fn from_char(c: char) -> Option<Direction> {
+        match c {
+            '^' | 'N' | 'n' | 'U' | 'u' => Some(Direction::North),
+            '>' | 'E' | 'e' | 'R' | 'r' => Some(Direction::East),
+            'v' | 'S' | 's' | 'D' | 'd' => Some(Direction::South),
+            '<' | 'W' | 'w' | 'L' | 'l' => Some(Direction::West),
+            _ => None,
+        }
+    }
+
+    pub fn to_arrow(&self) -> char {
+        match self {
+            Direction::North => '^',
+            Direction::East => '>',
+            Direction::South => 'v',
+            Direction::West => '<',
+        }
+    }
+
+    pub fn turn_left(&self) -> Direction {
+        Direction::ALL[(*self as usize + 3) % 4]
+    }
+
+    pub fn turn_right(&self) -> Direction {
+        Direction::ALL[(*self as usize + 1) % 4]
+    }
+
+    pub fn reverse(&self) -> Direction {
+        Direction::ALL[(*self as usize + 2) % 4]
+    }
+
+    pub fn turn(&self, turn: Turn) -> Direction {
+        match turn {
+            Turn::Left => self.turn_left(),
+            Turn::Right => self.turn_right(),
+        }
+    }
+
+    pub fn offset(&self) -> (i64, i64) {
+        match self {
+            Direction::North => (-1, 0),
+            Direction::East => (0, 1),
+            Direction::South => (1, 0),
+            Direction::West => (0, -1),
+        }
+    }
+
+    /// The point one step from `pt` in this direction.
+    pub fn step(&self, pt: (i64, i64)) -> (i64, i64) {
+        let offset = self.offset();
+        (pt.0 + offset.0, pt.1 + offset.1)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Turn {
+    Left,
+    Right,
+}
+
+impl Turn {
+    /// Parse an `L` or `R` token.
+    pub fn from_char(c: char) -> Option<Turn> {
+        match c {
+            'L' | 'l' => Some(Turn::Left),
+            'R' | 'r' => Some(Turn::Right),
+            _ => None,
+        }
+    }
+}
+
+type Vec3 = (i64, i64, i64);
+
+fn neg(a: Vec3) -> Vec3 {
+    (-a.0, -a.1, -a.2)
+}
+
+fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    (
+        a.1 * b.2 - a.2 * b.1,
+        a.2 * b.0 - a.0 * b.2,
+        a.0 * b.1 - a.1 * b.0,
+    )
+}
+
+/// One face of a folded cube net, with where its rows and columns point in
+/// 3D once folded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct CubeFace {
+    block: (i64, i64),
+    normal: Vec3,
+    right: Vec3,
+    down: Vec3,
+}
+
+impl CubeFace {
+    fn direction_3d(&self, dir: Direction) -> Vec3 {
+        match dir {
+            Direction::North => neg(self.down),
+            Direction::East => self.right,
+            Direction::South => self.down,
+            Direction::West => neg(self.right),
+        }
+    }
+
+    fn direction_2d(&self, v: Vec3) -> Direction {
+        *Direction::ALL
+            .iter()
+            .find(|&&dir| self.direction_3d(dir) == v)
+            .expect("Vector doesn't lie in the plane of the face")
+    }
+
+    /// Distance along the 3D axis `v` of the local point `(r, c)`.
+    fn along(&self, v: Vec3, local: (i64, i64), size: i64) -> i64 {
+        if v == self.right {
+            local.1
+        } else if v == neg(self.right) {
+            size - 1 - local.1
+        } else if v == self.down {
+            local.0
+        } else {
+            size - 1 - local.0
+        }
+    }
+
+    /// The face you reach by stepping off the edge in direction `dir`, tipping
+    /// the cube over that edge.
+    fn roll(&self, dir: Direction, block: (i64, i64)) -> CubeFace {
+        let mut face = *self;
+        face.block = block;
+        match dir {
+            Direction::North => {
+                face.normal = neg(self.down);
+                face.down = self.normal;
+            }
+            Direction::East => {
+                face.normal = self.right;
+                face.right = neg(self.normal);
+            }
+            Direction::South => {
+                face.normal = self.down;
+                face.down = neg(self.normal);
+            }
+            Direction::West => {
+                face.normal = neg(self.right);
+                face.right = self.normal;
+            }
+        }
+        face
+    }
+}
+
+/// A cube net laid out on a grid, folded up so walking off one face carries
+/// on along the adjoining face of the cube.
+#[derive(Clone, Debug)]
+pub struct CubeNet {
+    pub size: i64,
+    faces: Vec<CubeFace>,
+}
+
+impl CubeNet {
+    /// Fold the net made up of the points in `rows` x `cols` where `on_net`
+    /// is true, with each face `size` x `size` points. Returns `None` if that
+    /// doesn't give 6 connected faces that fold into a cube.
+    pub fn new<F>(size: i64, rows: i64, cols: i64, on_net: F) -> Option<CubeNet>
+    where
+        F: Fn((i64, i64)) -> bool,
+    {
+        let mut blocks = vec![];
+        for br in 0..rows / size {
+            for bc in 0..cols / size {
+                if on_net((br * size, bc * size)) {
+                    blocks.push((br, bc));
+                }
+            }
+        }
+        if blocks.len() != 6 {
+            return None;
+        }
+
+        let mut faces = vec![CubeFace {
+            block: blocks[0],
+            normal: (0, 0, 1),
+            right: (1, 0, 0),
+            down: (0, 1, 0),
+        }];
+        let mut idx = 0;
+        while idx < faces.len() {
+            let face = faces[idx];
+            for dir in Direction::ALL {
+                let block = dir.step(face.block);
+                if blocks.contains(&block) && faces.iter().all(|f| f.block != block) {
+                    faces.push(face.roll(dir, block));
+                }
+            }
+            idx += 1;
+        }
+
+        let mut normals: Vec<Vec3> = faces.iter().map(|f| f.normal).collect();
+        normals.sort();
+        normals.dedup();
+        if faces.len() != 6 || normals.len() != 6 {
+            return None;
+        }
+        Some(CubeNet { size, faces })
+    }
+
+    fn face_at(&self, pt: (i64, i64)) -> Option<&CubeFace> {
+        if pt.0 < 0 || pt.1 < 0 {
+            return None;
+        }
+        let block = (pt.0 / self.size, pt.1 / self.size);
+        self.faces.iter().find(|f| f.block == block)
+    }
+
+    /// Where you end up, and which way you're facing, after one step from
+    /// `pt` in direction `dir`.
+    pub fn step(&self, pt: (i64, i64), dir: Direction) -> ((i64, i64), Direction) {
+        let next = dir.step(pt);
+        if self.face_at(next).is_some() {
+            return (next, dir);
+        }
+
+        let n = self.size;
+        let from = self.face_at(pt).expect("Point isn't on the cube net");
+        let normal = from.direction_3d(dir);
+        let to = self.faces.iter().find(|f| f.normal == normal).unwrap();
+
+        // Keep the same distance along the shared edge
+        let edge = cross(from.normal, to.normal);
+        let local = (pt.0 - from.block.0 * n, pt.1 - from.block.1 * n);
+        let t = from.along(edge, local, n);
+
+        let new_dir = to.direction_2d(neg(from.normal));
+        let local = match new_dir {
+            Direction::North | Direction::South => {
+                let r = if new_dir == Direction::South {
+                    0
+                } else {
+                    n - 1
+                };
+                let c = if to.right == edge { t } else { n - 1 - t };
+                (r, c)
+            }
+            Direction::East | Direction::West => {
+                let r = if to.down == edge { t } else { n - 1 - t };
+                let c = if new_dir == Direction::East { 0 } else { n - 1 };
+                (r, c)
+            }
+        };
+        (
+            (to.block.0 * n + local.0, to.block.1 * n + local.1),
+            new_dir,
+        )
+    }
+}
+
+/// What a `Walker` does when a step would take it off the grid.
+#[derive(Clone, Debug)]
+pub enum EdgePolicy {
+    /// Come back in on the opposite side.
+    Wrap,
+    /// Stay put.
+    Clamp,
+    /// Fold over onto the next face of the cube.
+    CubeNet(CubeNet),
+}
+
+/// Walks a position around a `rows` x `cols` grid.
+#[derive(Clone, Debug)]
+pub struct Walker {
+    pub pos: (i64, i64),
+    pub dir: Direction,
+    pub rows: i64,
+    pub cols: i64,
+    pub policy: EdgePolicy,
+}
+
+impl Walker {
+    pub fn new(pos: (i64, i64), dir: Direction, rows: i64, cols: i64, policy: EdgePolicy) -> Self {
+        Self {
+            pos,
+            dir,
+            rows,
+            cols,
+            policy,
+        }
+    }
+
+    pub fn turn(&mut self, turn: Turn) {
+        self.dir = self.dir.turn(turn);
+    }
+
+    /// Position and direction after the next step, without taking it.
+    pub fn peek(&self) -> ((i64, i64), Direction) {
+        match &self.policy {
+            EdgePolicy::Wrap => {
+                let next = self.dir.step(self.pos);
+                (
+                    (next.0.rem_euclid(self.rows), next.1.rem_euclid(self.cols)),
+                    self.dir,
+                )
+            }
+            EdgePolicy::Clamp => {
+                let next = self.dir.step(self.pos);
+                if next.0 < 0 || next.1 < 0 || next.0 >= self.rows || next.1 >= self.cols {
+                    (self.pos, self.dir)
+                } else {
+                    (next, self.dir)
+                }
+            }
+            EdgePolicy::CubeNet(net) => net.step(self.pos, self.dir),
+        }
+    }
+
+    pub fn step(&mut self) {
+        (self.pos, self.dir) = self.peek();
+    }
+
+    /// Step only if `allowed` is true for the square being stepped onto,
+    /// e.g. to stop at walls. Returns whether the step was taken.
+    pub fn try_step<F>(&mut self, allowed: F) -> bool
+    where
+        F: Fn((i64, i64)) -> bool,
+    {
+        let (pos, dir) = self.peek();
+        if allowed(pos) {
+            self.pos = pos;
+            self.dir = dir;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// All points reachable from `start` by orthogonal steps for which
+/// `connected(from, to)` is true, including `start` itself.
+pub fn flood_fill<F>(start: (i64, i64), connected: F) -> HashSet<(i64, i64)>
+where
+    F: Fn((i64, i64), (i64, i64)) -> bool,
+{
+    let mut output = HashSet::new();
+    let mut stack = vec![start];
+    output.insert(start);
+    while let Some(pt) = stack.pop() {
+        for n in get_orthogonal_neighbors(pt) {
+            if !output.contains(&n) && connected(pt, n) {
+                output.insert(n);
+                stack.push(n);
+            }
+        }
+    }
+    output
+}
+
+/// An orthogonally connected group of points.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Region {
+    pub points: HashSet<(i64, i64)>,
+    /// Number of unit edges between the region and the outside.
+    pub perimeter: usize,
+    /// Number of straight sides, counting the sides of any holes too. This
+    /// is the same as the number of corners.
+    pub sides: usize,
+    /// Inclusive `(min, max)` corners of the bounding box.
+    pub bounds: ((i64, i64), (i64, i64)),
+}
+
+impl Region {
+    pub fn new(points: HashSet<(i64, i64)>) -> Self {
+        let mut perimeter = 0;
+        let mut sides = 0;
+        let mut min = (i64::MAX, i64::MAX);
+        let mut max = (i64::MIN, i64::MIN);
+        for &pt in &points {
+            perimeter += get_orthogonal_neighbors(pt)
+                .iter()
+                .filter(|n| !points.contains(n))
+                .count();
+
+            // Check each of the 4 corners of the square for a convex or
+            // concave corner of the region.
+            for dir in Direction::ALL {
+                let a = dir.step(pt);
+                let b = dir.turn_right().step(pt);
+                let diagonal = dir.turn_right().step(a);
+                let (in_a, in_b) = (points.contains(&a), points.contains(&b));
+                if (!in_a && !in_b) || (in_a && in_b && !points.contains(&diagonal)) {
+                    sides += 1;
+                }
+            }
+
+            min = (min.0.min(pt.0), min.1.min(pt.1));
+            max = (max.0.max(pt.0), max.1.max(pt.1));
+        }
+
+        Self {
+            points,
+            perimeter,
+            sides,
+            bounds: (min, max),
+        }
+    }
+
+    pub fn area(&self) -> usize {
+        self.points.len()
+    }
+}
+
+/// Split `points` into regions, where neighboring points belong to the same
+/// region if `connected(a, b)` is true. Regions are ordered by their
+/// smallest point, so the index into the output works as a stable label.
+pub fn label_regions<I, F>(points: I, connected: F) -> Vec<Region>
+where
+    I: IntoIterator<Item = (i64, i64)>,
+    F: Fn((i64, i64), (i64, i64)) -> bool,
+{
+    let mut points: Vec<(i64, i64)> = points.into_iter().collect();
+    points.sort();
+    let all: HashSet<(i64, i64)> = points.iter().copied().collect();
+
+    let mut seen: HashSet<(i64, i64)> = HashSet::new();
+    let mut output = vec![];
+    for pt in points {
+        if seen.contains(&pt) {
+            continue;
+        }
+        let region = flood_fill(pt, |a, b| all.contains(&b) && connected(a, b));
+        seen.extend(region.iter().copied());
+        output.push(Region::new(region));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::shortest_path;
+
+    const MAZE: [&str; 5] = ["..#....", ".##.##.", "....#..", "#.#.#.#", "..#...."];
+
+    fn maze() -> SizedBoard<char, 5, 7> {
+        let mut squares = [['.'; 7]; 5];
+        for (r, line) in MAZE.iter().enumerate() {
+            for (c, ch) in line.chars().enumerate() {
+                squares[r][c] = ch;
+            }
+        }
+        SizedBoard { squares }
+    }
+
+    #[test]
+    fn connected() {
+        let board = maze();
+        let graph = board.connected(|_, to| board.squares[to.0][to.1] != '#');
+        assert_eq!(graph.get_neighbors(&(0, 0)), vec![(0, 1), (1, 0)]);
+        assert_eq!(graph.get_neighbors(&(2, 3)), vec![(1, 3), (2, 2), (3, 3)]);
+
+        let path = shortest_path(&(0, 0), &(4, 6), &graph).unwrap();
+        assert_eq!(path.len() - 1, 10);
+    }
+
+    #[test]
+    fn connected_one_way() {
+        // Only allow stepping down or right
+        let board = maze();
+        let graph = board.connected(|from, to| {
+            board.squares[to.0][to.1] != '#' && (to.0 > from.0 || to.1 > from.1)
+        });
+        assert_eq!(graph.get_neighbors(&(0, 0)), vec![(0, 1), (1, 0)]);
+        assert_eq!(graph.get_neighbors(&(2, 3)), vec![(3, 3)]);
+        assert_eq!(graph.get_neighbors(&(4, 6)), vec![]);
+    }
+
+    fn life(alive: &bool, neighbors: &[bool]) -> bool {
+        let count = neighbors.iter().filter(|&&n| n).count();
+        count == 3 || (*alive && count == 2)
+    }
+
+    #[test]
+    fn automaton_sparse() {
+        // Blinker flips between horizontal and vertical every generation
+        let mut grid: HashMap<(i64, i64), bool> = HashMap::new();
+        for x in 0..3 {
+            grid.insert((x, 0), true);
+        }
+        let mut automaton = Automaton::new(grid.clone(), Neighborhood::Moore, life);
+        assert!(automaton.step());
+        let mut vertical = HashMap::new();
+        for y in -1..2 {
+            vertical.insert((1, y), true);
+        }
+        assert_eq!(automaton.grid, vertical);
+
+        let mut automaton = Automaton::new(grid, Neighborhood::Moore, life);
+        assert_eq!(automaton.run_until_repeat(), (0, 2));
+        assert_eq!(automaton.generation, 2);
+
+        // Stored dead cells, as from parsing every '.', make no difference
+        let parse = |rows: [&str; 4]| -> HashMap<(i64, i64), bool> {
+            let mut grid = HashMap::new();
+            for (y, row) in rows.iter().enumerate() {
+                for (x, c) in row.chars().enumerate() {
+                    grid.insert((x as i64, y as i64), c == '#');
+                }
+            }
+            grid
+        };
+        let block = parse(["....", ".##.", ".##.", "...."]);
+        let mut automaton = Automaton::new(block, Neighborhood::Moore, life);
+        assert_eq!(automaton.grid.len(), 4);
+        assert_eq!(automaton.run_until_stable(), 0);
+        let blinker = parse([".#..", ".#..", ".#..", "...."]);
+        let mut automaton = Automaton::new(blinker, Neighborhood::Moore, life);
+        assert_eq!(automaton.run_until_repeat(), (0, 2));
+    }
+
+    #[test]
+    fn automaton_dense() {
+        // Block is a still life
+        let mut squares = [[false; 4]; 4];
+        squares[1][1] = true;
+        squares[1][2] = true;
+        squares[2][1] = true;
+        squares[2][2] = true;
+        let board = SizedBoard { squares };
+        let mut automaton = Automaton::new(board, Neighborhood::Moore, life);
+        assert_eq!(automaton.run_until_stable(), 0);
+        assert_eq!(automaton.grid, board);
+
+        // Spreading to orthogonal neighbors fills a 3x5 board in 6 steps, the
+        // distance from one corner to the other
+        let mut squares = [[0_u8; 5]; 3];
+        squares[0][0] = 1;
+        let board = SizedBoard { squares };
+        let mut automaton = Automaton::new(board, Neighborhood::VonNeumann, |c, n| {
+            if n.contains(&1) {
+                1
+            } else {
+                *c
+            }
+        });
+        assert_eq!(automaton.run_until_stable(), 6);
+        assert_eq!(automaton.grid.squares, [[1; 5]; 3]);
+        assert_eq!(automaton.run_until_repeat(), (7, 1));
+    }
+
+    #[test]
+    fn directions() {
+        let dirs: Vec<_> = "^>v<".chars().filter_map(Direction::from_char).collect();
+        assert_eq!(dirs, Direction::ALL);
+        let dirs: Vec<_> = "NESW".chars().filter_map(Direction::from_char).collect();
+        assert_eq!(dirs, Direction::ALL);
+        let dirs: Vec<_> = "URDL".chars().filter_map(Direction::from_char).collect();
+        assert_eq!(dirs, Direction::ALL);
+        assert_eq!(Direction::from_char('x'), None);
+
+        assert_eq!(Direction::North.turn_left(), Direction::West);
+        assert_eq!(Direction::West.turn_right(), Direction::North);
+        assert_eq!(Direction::East.reverse(), Direction::West);
+        assert_eq!(
+            Direction::South.turn(Turn::from_char('R').unwrap()),
+            Direction::West
+        );
+        assert_eq!(Direction::South.step((3, 3)), (4, 3));
+        assert_eq!(Direction::South.to_arrow(), 'v');
+
+        assert_eq!(manhattan((1, -2), (-3, 4)), 10);
+        assert_eq!(chebyshev((1, -2), (-3, 4)), 6);
+    }
+
+    #[test]
+    fn walker() {
+        let mut walker = Walker::new((0, 1), Direction::North, 3, 4, EdgePolicy::Wrap);
+        walker.step();
+        assert_eq!(walker.pos, (2, 1));
+        walker.turn(Turn::Left);
+        walker.step();
+        walker.step();
+        assert_eq!(walker.pos, (2, 3));
+
+        let mut walker = Walker::new((0, 1), Direction::North, 3, 4, EdgePolicy::Clamp);
+        walker.step();
+        assert_eq!(walker.pos, (0, 1));
+        walker.turn(Turn::Right);
+        assert!(walker.try_step(|_| true));
+        assert!(!walker.try_step(|pt| pt != (0, 3)));
+        assert_eq!(walker.pos, (0, 2));
+    }
+
+    #[test]
+    fn walker_cube() {
+        let map = [
+            "        ...#",
+            "        .#..",
+            "        #...",
+            "        ....",
+            "...#.......#",
+            "........#...",
+            "..#....#....",
+            "..........#.",
+            "        ...#....",
+            "        .....#..",
+            "        .#......",
+            "        ......#.",
+        ];
+        let tile = |pt: (i64, i64)| {
+            map.get(pt.0 as usize)
+                .and_then(|line| line.chars().nth(pt.1 as usize))
+                .unwrap_or(' ')
+        };
+        let net = CubeNet::new(4, 12, 16, |pt| tile(pt) != ' ').unwrap();
+        assert!(CubeNet::new(4, 12, 16, |pt| pt.0 < 4).is_none());
+
+        let mut walker = Walker::new((0, 8), Direction::East, 12, 16, EdgePolicy::CubeNet(net));
+        let path = "10R5L5R10L4R5L5";
+        let mut steps = 0;
+        for c in path.chars() {
+            if let Some(turn) = Turn::from_char(c) {
+                for _ in 0..steps {
+                    walker.try_step(|pt| tile(pt) != '#');
+                }
+                steps = 0;
+                walker.turn(turn);
+            } else {
+                steps = 10 * steps + c.to_digit(10).unwrap();
+            }
+        }
+        for _ in 0..steps {
+            walker.try_step(|pt| tile(pt) != '#');
+        }
+        assert_eq!(walker.pos, (4, 6));
+        assert_eq!(walker.dir, Direction::North);
+    }
+
+    fn price(input: &str) -> (usize, usize) {
+        let grid: HashMap<(i64, i64), char> = input
+            .lines()
+            .enumerate()
+            .flat_map(|(r, line)| {
+                line.chars()
+                    .enumerate()
+                    .map(move |(c, ch)| ((r as i64, c as i64), ch))
+            })
+            .collect();
+        let regions = label_regions(grid.keys().copied(), |a, b| grid[&a] == grid[&b]);
+        let perimeter = regions.iter().map(|r| r.area() * r.perimeter).sum();
+        let sides = regions.iter().map(|r| r.area() * r.sides).sum();
+        (perimeter, sides)
+    }
+
+    #[test]
+    fn regions() {
+        assert_eq!(price("AAAA\nBBCD\nBBCC\nEEEC"), (140, 80));
+        assert_eq!(price("OOOOO\nOXOXO\nOOOOO\nOXOXO\nOOOOO"), (772, 436));
+        assert_eq!(price("EEEEE\nEXXXX\nEEEEE\nEXXXX\nEEEEE").1, 236);
+        assert_eq!(
+            price("AAAAAA\nAAABBA\nAAABBA\nABBAAA\nABBAAA\nAAAAAA").1,
+            368
+        );
+
+        let points = vec![(0, 0), (0, 1), (1, 1), (2, 1), (2, 2), (2, 3)];
+        let regions = label_regions(points, |_, _| true);
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].bounds, ((0, 0), (2, 3)));
+    }
+
+    #[test]
+    fn flood() {
+        let board = maze();
+        let filled = flood_fill((0, 0), |_, to| {
+            to.0 >= 0
+                && to.1 >= 0
+                && to.0 < 5
+                && to.1 < 7
+                && board.squares[to.0 as usize][to.1 as usize] != '#'
+        });
+        assert_eq!(filled.len(), 24);
+        assert!(!filled.contains(&(0, 2)));
+
+        let regions = board.regions();
+        assert_eq!(regions.len(), 6);
+        assert_eq!(regions[0].points, filled);
+        assert_eq!(regions[1].bounds, ((0, 1), (1, 2)));
+    }
 }
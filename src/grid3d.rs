@@ -0,0 +1,58 @@
+/// Which of the surrounding voxels count as neighbors.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Neighborhood {
+    /// The 6 voxels sharing a face.
+    Faces,
+    /// The 18 voxels sharing a face or an edge.
+    Edges,
+    /// All 26 surrounding voxels.
+    Corners,
+}
+
+impl Neighborhood {
+    pub fn neighbors(&self, pt: (i64, i64, i64)) -> Vec<(i64, i64, i64)> {
+        // Number of axes an offset is allowed to move along
+        let max_axes = match self {
+            Neighborhood::Faces => 1,
+            Neighborhood::Edges => 2,
+            Neighborhood::Corners => 3,
+        };
+
+        let mut output = vec![];
+        for dx in -1..=1_i64 {
+            for dy in -1..=1_i64 {
+                for dz in -1..=1_i64 {
+                    let axes = dx.abs() + dy.abs() + dz.abs();
+                    if axes > 0 && axes <= max_axes {
+                        output.push((pt.0 + dx, pt.1 + dy, pt.2 + dz));
+                    }
+                }
+            }
+        }
+        output
+    }
+}
+
+/// Sum of the absolute coordinate differences between two points.
+pub fn manhattan(a: (i64, i64, i64), b: (i64, i64, i64)) -> i64 {
+    (a.0 - b.0).abs() + (a.1 - b.1).abs() + (a.2 - b.2).abs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn neighbors() {
+        let pt = (1, -2, 3);
+        let faces = Neighborhood::Faces.neighbors(pt);
+        let edges = Neighborhood::Edges.neighbors(pt);
+        let corners = Neighborhood::Corners.neighbors(pt);
+        assert_eq!(faces.len(), 6);
+        assert_eq!(edges.len(), 18);
+        assert_eq!(corners.len(), 26);
+        assert!(faces.iter().all(|&n| manhattan(pt, n) == 1));
+        assert!(edges.iter().all(|&n| manhattan(pt, n) <= 2));
+        assert!(!corners.contains(&pt));
+    }
+}
@@ -0,0 +1,166 @@
+use std::ops::{Add, Sub};
+
+/// Hex grid position in axial coordinates. The implied third cube
+/// coordinate is `s = -q - r`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Hex {
+    pub q: i64,
+    pub r: i64,
+}
+
+impl Hex {
+    pub fn new(q: i64, r: i64) -> Self {
+        Self { q, r }
+    }
+
+    pub fn from_cube(q: i64, r: i64, s: i64) -> Self {
+        if q + r + s != 0 {
+            panic!("Cube coordinates must satisfy q + r + s == 0");
+        }
+        Self { q, r }
+    }
+
+    pub fn s(&self) -> i64 {
+        -self.q - self.r
+    }
+
+    pub fn cube(&self) -> (i64, i64, i64) {
+        (self.q, self.r, self.s())
+    }
+
+    /// Number of steps from the origin.
+    pub fn length(&self) -> i64 {
+        (self.q.abs() + self.r.abs() + self.s().abs()) / 2
+    }
+
+    pub fn distance(&self, other: Hex) -> i64 {
+        (*self - other).length()
+    }
+
+    /// The 6 adjacent hexes, going around counterclockwise. These are the
+    /// same whichever way the hexes are drawn.
+    pub fn neighbors(&self) -> Vec<Hex> {
+        AXIAL_DIRECTIONS
+            .iter()
+            .map(|&(q, r)| *self + Hex::new(q, r))
+            .collect()
+    }
+}
+
+impl Add for Hex {
+    type Output = Hex;
+
+    fn add(self, other: Hex) -> Hex {
+        Hex::new(self.q + other.q, self.r + other.r)
+    }
+}
+
+impl Sub for Hex {
+    type Output = Hex;
+
+    fn sub(self, other: Hex) -> Hex {
+        Hex::new(self.q - other.q, self.r - other.r)
+    }
+}
+
+const AXIAL_DIRECTIONS: [(i64, i64); 6] = [(1, 0), (1, -1), (0, -1), (-1, 0), (-1, 1), (0, 1)];
+
+/// Which way up the hexes are drawn. This only changes what the directions
+/// are called: pointy hexes have `e` and `w` neighbors, flat hexes have `n`
+/// and `s` neighbors, and both have `ne`, `nw`, `se` and `sw`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Orientation {
+    Pointy,
+    Flat,
+}
+
+impl Orientation {
+    /// Direction names matching `AXIAL_DIRECTIONS` order.
+    fn names(&self) -> [&'static str; 6] {
+        match self {
+            Orientation::Pointy => ["e", "ne", "nw", "w", "sw", "se"],
+            Orientation::Flat => ["se", "ne", "n", "nw", "sw", "s"],
+        }
+    }
+
+    /// Offset for a single direction name like `ne`, case insensitive.
+    pub fn offset(&self, dir: &str) -> Option<Hex> {
+        let dir = dir.to_lowercase();
+        self.names()
+            .iter()
+            .position(|&name| name == dir)
+            .map(|idx| Hex::new(AXIAL_DIRECTIONS[idx].0, AXIAL_DIRECTIONS[idx].1))
+    }
+
+    /// Parse a list of moves into offsets. Moves can be separated by commas
+    /// or whitespace (`ne,ne,s`) or run together (`esenee`).
+    pub fn parse_moves(&self, input: &str) -> Option<Vec<Hex>> {
+        let chars: Vec<char> = input.chars().collect();
+        let mut output = vec![];
+        let mut idx = 0;
+        while idx < chars.len() {
+            if chars[idx] == ',' || chars[idx].is_whitespace() {
+                idx += 1;
+                continue;
+            }
+
+            // Prefer the two letter directions
+            if idx + 1 < chars.len() {
+                let token: String = chars[idx..idx + 2].iter().collect();
+                if let Some(offset) = self.offset(&token) {
+                    output.push(offset);
+                    idx += 2;
+                    continue;
+                }
+            }
+            output.push(self.offset(&chars[idx].to_string())?);
+            idx += 1;
+        }
+        Some(output)
+    }
+
+    /// Follow `input` moves from the origin, returning where you end up.
+    pub fn walk(&self, input: &str) -> Option<Hex> {
+        let moves = self.parse_moves(input)?;
+        Some(
+            moves
+                .into_iter()
+                .fold(Hex::new(0, 0), |pos, step| pos + step),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cube() {
+        let hex = Hex::from_cube(2, -3, 1);
+        assert_eq!(hex, Hex::new(2, -3));
+        assert_eq!(hex.cube(), (2, -3, 1));
+        assert_eq!(hex.length(), 3);
+        assert_eq!(hex.distance(Hex::new(1, -1)), 2);
+        assert_eq!(hex.neighbors().len(), 6);
+        assert!(hex.neighbors().iter().all(|n| n.distance(hex) == 1));
+    }
+
+    #[test]
+    fn flat() {
+        let flat = Orientation::Flat;
+        assert_eq!(flat.walk("ne,ne,ne").unwrap().length(), 3);
+        assert_eq!(flat.walk("ne,ne,sw,sw").unwrap().length(), 0);
+        assert_eq!(flat.walk("ne,ne,s,s").unwrap().length(), 2);
+        assert_eq!(flat.walk("se,sw,se,sw,sw").unwrap().length(), 3);
+        assert_eq!(flat.offset("e"), None);
+    }
+
+    #[test]
+    fn pointy() {
+        let pointy = Orientation::Pointy;
+        assert_eq!(pointy.walk("nwwswee"), Some(Hex::new(0, 0)));
+        assert_eq!(pointy.walk("esew"), pointy.offset("se"));
+        assert_eq!(pointy.parse_moves("sesenwnenenewsee").unwrap().len(), 9);
+        assert_eq!(pointy.parse_moves("nx"), None);
+    }
+}
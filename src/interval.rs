@@ -19,10 +19,14 @@ impl Interval {
         (self.max - self.min + 1) as usize
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     pub fn union(&self, other: Interval) -> Vec<Interval> {
         if self.min > other.max || self.max < other.min {
             // Disjoint intervals
-            return vec![*self, other];
+            vec![*self, other]
         } else {
             // Overlapping, so create a new bigger Interval
             let new_max = if self.max > other.max {
@@ -35,14 +39,14 @@ impl Interval {
             } else {
                 other.min
             };
-            return vec![Interval::new(new_min, new_max)];
+            vec![Interval::new(new_min, new_max)]
         }
     }
 
     pub fn intersect(&self, other: Interval) -> Option<Interval> {
         if self.min > other.max || self.max < other.min {
             // Disjoint intervals
-            return None;
+            None
         } else {
             // Overlapping, so there is some valid intersection
             let new_max = if self.max < other.max {
@@ -62,7 +66,7 @@ impl Interval {
     pub fn difference(&self, other: Interval) -> Vec<Interval> {
         if self.min > other.max || self.max < other.min {
             // Disjoint intervals
-            return vec![*self];
+            vec![*self]
         } else {
             // Overlapping, so either split in two, or on either side.
             if other.min <= self.min {
@@ -72,28 +76,28 @@ impl Interval {
                     return vec![];
                 }
                 let newint = Interval::new(other.max + 1, self.max);
-                if newint.len() > 0 {
-                    return vec![newint];
+                if !newint.is_empty() {
+                    vec![newint]
                 } else {
-                    return vec![];
+                    vec![]
                 }
             } else if other.max >= self.max {
                 // Clipping off right side
                 let newint = Interval::new(self.min, other.min - 1);
-                if newint.len() > 0 {
-                    return vec![newint];
+                if !newint.is_empty() {
+                    vec![newint]
                 } else {
-                    return vec![];
+                    vec![]
                 }
             } else {
                 // Split into 2. Check sizes of both new Intervals.
                 let int1 = Interval::new(self.min, other.min - 1);
                 let int2 = Interval::new(other.max + 1, self.max);
                 let mut output = vec![];
-                if int1.len() > 0 {
+                if !int1.is_empty() {
                     output.push(int1);
                 }
-                if int2.len() > 0 {
+                if !int2.is_empty() {
                     output.push(int2);
                 }
                 output
@@ -1,6 +1,9 @@
 pub mod csg;
+mod diagram;
 pub mod graph;
 pub mod grid2d;
+pub mod grid3d;
+pub mod hex;
 pub mod interval;
 pub mod linked_list;
 pub mod tree;
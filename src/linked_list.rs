@@ -35,6 +35,12 @@ pub struct LinkedList<T> {
     pub size: usize,
 }
 
+impl<T: Copy + Debug> Default for LinkedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T: Copy + Debug> LinkedList<T> {
     pub fn new() -> Self {
         Self {
@@ -58,6 +64,10 @@ impl<T: Copy + Debug> LinkedList<T> {
         self.size
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
     pub fn push_tail(&mut self, value: T) -> usize {
         let mut new_node = LinkedListNode::new(value);
         new_node.head = self.tail;
@@ -153,17 +163,13 @@ impl<T: Copy + Debug> LinkedList<T> {
             let mut id = hid;
             print!("{:?} ", self.data[id].value);
             if Some(id) == self.data[id].next() {
-                println!("");
+                println!();
                 return;
             }
 
-            loop {
-                if let Some(next_id) = self.data[id].next() {
-                    id = next_id;
-                    print!("{:?} ", self.data[id].value);
-                } else {
-                    break;
-                }
+            while let Some(next_id) = self.data[id].next() {
+                id = next_id;
+                print!("{:?} ", self.data[id].value);
                 if id == self.tail.unwrap() {
                     break;
                 }
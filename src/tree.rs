@@ -1,4 +1,6 @@
-use std::collections::{HashMap, HashSet};
+use crate::diagram::{Diagram, Format};
+use std::fmt;
+use std::io;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct TreeNode<T: Clone + PartialEq + Eq> {
@@ -25,6 +27,12 @@ pub struct Tree<T: Clone + PartialEq + Eq> {
     pub nodes: Vec<TreeNode<T>>,
 }
 
+impl<T: Clone + PartialEq + Eq> Default for Tree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T: Clone + PartialEq + Eq> Tree<T> {
     pub fn new() -> Self {
         Self {
@@ -77,7 +85,7 @@ impl<T: Clone + PartialEq + Eq> Tree<T> {
     pub fn leaves(&self) -> Vec<usize> {
         self.nodes
             .iter()
-            .filter(|n| n.children.len() == 0)
+            .filter(|n| n.children.is_empty())
             .map(|n| n.id)
             .collect()
     }
@@ -97,11 +105,45 @@ impl<T: Clone + PartialEq + Eq> Tree<T> {
         self.nodes.len()
     }
 
-    pub fn iter(&self) -> TreeIter<T> {
-        TreeIter::new(&self)
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn iter(&self) -> TreeIter<'_, T> {
+        TreeIter::new(self)
+    }
+}
+
+impl<T: Clone + PartialEq + Eq + fmt::Display> Tree<T> {
+    /// Write the tree in Graphviz DOT format, with edges from parents to
+    /// children and nodes labelled with their values. The nodes of `path`,
+    /// and the edges between consecutive ones, are highlighted in red.
+    pub fn write_dot<W: io::Write>(&self, w: &mut W, path: &[usize]) -> io::Result<()> {
+        self.diagram(path).write(w, Format::Dot)
+    }
+
+    /// Write the tree as a Mermaid flowchart, highlighting `path` like
+    /// `write_dot`.
+    pub fn write_mermaid<W: io::Write>(&self, w: &mut W, path: &[usize]) -> io::Result<()> {
+        self.diagram(path).write(w, Format::Mermaid)
+    }
+
+    fn diagram(&self, path: &[usize]) -> Diagram {
+        Diagram {
+            directed: true,
+            nodes: self.nodes.iter().map(|n| n.value.to_string()).collect(),
+            edges: self
+                .nodes
+                .iter()
+                .flat_map(|n| n.children.iter().map(move |&c| (n.id, c, None)))
+                .collect(),
+            path: path.to_vec(),
+        }
     }
 }
 
+/// Iterator over the nodes of a `Tree` in depth first order, starting from
+/// the head, with each node's children visited in the order they were added.
 pub struct TreeIter<'a, T: Clone + PartialEq + Eq> {
     tree: &'a Tree<T>,
     idx: Option<usize>,
@@ -116,39 +158,28 @@ impl<'a, T: Clone + PartialEq + Eq> TreeIter<'a, T> {
     }
 }
 
-/*
 impl<'a, T: Clone + PartialEq + Eq> Iterator for TreeIter<'a, T> {
-    type Item = Vec<usize>;
+    type Item = &'a TreeNode<T>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut current = self.tree.nodes[self.idx];
-
-        // Go up to next place we have choices
-        let mut last = self.idx;
-        loop {
-            if let Some(parent) = current.parent {
-                self.idx = parent;
-                current = self.tree.nodes[parent];
-                let mut child_iter = current.children.iter();
-                loop {
-                    let temp = child_iter.next();
-                    if temp == Some(self.idx) || temp == None {
-                        break;
-                    }
-                }
-                let next = child_iter.next();
-                if next.is_some() {
-                    self.idx = next;
-                    return next;
-                }
-            } else {
-                // You're at the head
-                return None;
-            }
+        let node = &self.tree.nodes[self.idx?];
+
+        // Go down to the first child if there is one, else back up to the
+        // nearest next sibling
+        self.idx = node.children.first().copied();
+        let mut current = node;
+        while self.idx.is_none() {
+            let Some(parent) = current.parent else {
+                break;
+            };
+            let siblings = &self.tree.nodes[parent].children;
+            let pos = siblings.iter().position(|&c| c == current.id).unwrap();
+            self.idx = siblings.get(pos + 1).copied();
+            current = &self.tree.nodes[parent];
         }
+        Some(node)
     }
 }
-*/
 
 #[cfg(test)]
 mod tests {
@@ -178,4 +209,51 @@ mod tests {
         assert_eq!(path2, Some(vec![0, node4]));
         assert_eq!(tree.leaves(), vec![node4, node5]);
     }
+
+    #[test]
+    fn iter() {
+        assert_eq!(Tree::<usize>::new().iter().count(), 0);
+
+        let mut tree = Tree::with_head(0);
+        let node1 = tree.add_child_to_node(1, 0).unwrap();
+        let node2 = tree.add_child_to_node(2, 0).unwrap();
+        let node3 = tree.add_child_to_node(3, node1).unwrap();
+        tree.add_child_to_node(4, node3).unwrap();
+        tree.add_child_to_node(5, node1).unwrap();
+        tree.add_child_to_node(6, node2).unwrap();
+        let values: Vec<usize> = tree.iter().map(|n| n.value).collect();
+        assert_eq!(values, vec![0, 1, 3, 4, 5, 2, 6]);
+    }
+
+    #[test]
+    fn export() {
+        let mut tree = Tree::with_head("root");
+        let a = tree.add_child_to_node("a", 0).unwrap();
+        let b = tree.add_child_to_node("b", 0).unwrap();
+        let c = tree.add_child_to_node("c", a).unwrap();
+
+        let mut out = vec![];
+        tree.write_dot(&mut out, &tree.path_to_node(c).unwrap())
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "digraph {
+    n0 [label=\"root\", color=red, penwidth=2];
+    n1 [label=\"a\", color=red, penwidth=2];
+    n2 [label=\"b\"];
+    n3 [label=\"c\", color=red, penwidth=2];
+    n0 -> n1 [color=red, penwidth=2];
+    n0 -> n2;
+    n1 -> n3 [color=red, penwidth=2];
+}
+"
+        );
+
+        let mut out = vec![];
+        tree.write_mermaid(&mut out, &[b]).unwrap();
+        let mermaid = String::from_utf8(out).unwrap();
+        assert!(mermaid.starts_with("graph LR\n    n0[\"root\"]\n"));
+        assert!(mermaid.contains("    n1 --> n3\n"));
+        assert!(mermaid.ends_with("    style n2 stroke:red,stroke-width:3px\n"));
+    }
 }